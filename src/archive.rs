@@ -0,0 +1,100 @@
+// archive.rs - cache of built package archives, kept for rollback and downgrade
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::config::Config;
+use crate::vercmp::vercmp;
+
+// Package name and version of an archive path produced by makepkg
+// ("/x/foo-bar-1:1.0-1-x86_64.pkg.tar.zst" -> ("foo-bar", "1:1.0-1"))
+pub fn parse_name(path: &str) -> Option<(&str, String)> {
+    let file = path.rsplit('/').next()?;
+    let stem = &file[..file.find(".pkg.tar")?];
+    // <pkgname>-<pkgver>-<pkgrel>-<arch>
+    let mut parts = stem.rsplitn(4, '-');
+    let (_arch, rel, ver) = (parts.next()?, parts.next()?, parts.next()?);
+    Some((parts.next()?, format!("{}-{}", ver, rel)))
+}
+
+// Cached archives of package `name` as (version, path), newest first
+pub fn cached(cfg: &Config, name: &str) -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+    let entries = match fs::read_dir(cfg.archive_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Vec::new());
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+    let mut found = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let file = path.to_string_lossy();
+        if file.ends_with(".sig") {
+            continue;
+        }
+        if let Some((pkgname, version)) = parse_name(&file) && pkgname == name {
+            found.push((version, path.clone()));
+        }
+    }
+    found.sort_by(|a, b| vercmp(&b.0, &a.0));
+    Ok(found)
+}
+
+// Copy freshly built archives (and their signatures) into the cache, then drop all but the
+// `keep_archives` newest versions of each package. Does nothing if keep_archives is 0.
+pub fn store(cfg: &Config, files: &[String]) -> Result<(), Box<dyn Error>> {
+    if cfg.keep_archives == 0 || files.is_empty() {
+        return Ok(());
+    }
+    let dir = cfg.archive_dir();
+    fs::create_dir_all(&dir)?;
+    let mut names = Vec::new();
+    for file in files {
+        let src = Path::new(file);
+        let Some(file_name) = src.file_name() else {
+            continue;
+        };
+        fs::copy(src, dir.join(file_name))?;
+        let sig = PathBuf::from(format!("{}.sig", file));
+        if sig.is_file() {
+            fs::copy(&sig, dir.join(sig.file_name().unwrap_or_default()))?;
+        }
+        if let Some((name, _)) = parse_name(file) {
+            names.push(name);
+        }
+    }
+
+    for name in names {
+        for (_, path) in cached(cfg, name)?.iter().skip(cfg.keep_archives) {
+            fs::remove_file(path)?;
+            let _ = fs::remove_file(format!("{}.sig", path.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_versions() {
+        assert_eq!(
+            parse_name("/x/foo-bar-1:1.0-1-x86_64.pkg.tar.zst"),
+            Some(("foo-bar", "1:1.0-1".to_string()))
+        );
+        assert_eq!(
+            parse_name("foo-1.2.r3.gabc-2-any.pkg.tar.xz"),
+            Some(("foo", "1.2.r3.gabc-2".to_string()))
+        );
+    }
+
+    #[test]
+    fn not_archives() {
+        assert_eq!(parse_name("PKGBUILD"), None);
+        assert_eq!(parse_name("foo-1.0.pkg.tar.zst"), None);
+    }
+}
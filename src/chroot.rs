@@ -0,0 +1,58 @@
+// chroot.rs - clean chroot builds with devtools (mkarchroot, arch-nspawn, makechrootpkg)
+// The chroot directory holds the pristine "root" copy; makechrootpkg builds in a per-user copy of
+// it, so nothing a build installs ends up on the host.
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+use std::process::Command as Shell;
+
+use crate::config::Config;
+
+fn root(chroot_dir: &Path) -> PathBuf {
+    chroot_dir.join("root")
+}
+
+fn run(cfg: &Config, cmd: &mut Shell) -> Result<bool, Box<dyn Error>> {
+    crate::run_cmd(cfg, cmd).map_err(|e| -> Box<dyn Error> {
+        if e.kind() == io::ErrorKind::NotFound {
+            format!(
+                "{} not found; --chroot needs devtools (pacman -S devtools)",
+                cmd.get_program().to_string_lossy()
+            ).into()
+        } else {
+            e.into()
+        }
+    })
+}
+
+// Create the chroot with base-devel on first use, otherwise bring it up to date
+pub fn prepare(cfg: &Config) -> Result<(), Box<dyn Error>> {
+    let dir = cfg.chroot_dir();
+    let root = root(&dir);
+    let ok = if root.is_dir() {
+        println!("Updating chroot {}", dir.display());
+        run(cfg, Shell::new("arch-nspawn").arg(&root).args(["pacman", "-Syu", "--noconfirm"]))?
+    } else {
+        println!("Creating chroot {}", dir.display());
+        if !cfg.dry_run {
+            fs::create_dir_all(&dir)?;
+        }
+        run(cfg, Shell::new("mkarchroot").arg(&root).arg("base-devel"))?
+    };
+    if !ok {
+        return Err(format!("preparing the chroot in {} failed", dir.display()).into());
+    }
+    Ok(())
+}
+
+// Build the package in `dir` in a clean copy of the chroot. `inject` are package archives built
+// earlier (AUR dependencies) that are installed into the copy before building.
+pub fn build(cfg: &Config, dir: &Path, inject: &[String]) -> Result<bool, Box<dyn Error>> {
+    let mut cmd = Shell::new("makechrootpkg");
+    cmd.arg("-c").arg("-r").arg(cfg.chroot_dir()).current_dir(dir);
+    for file in inject {
+        cmd.args(["-I", file]);
+    }
+    run(cfg, &mut cmd)
+}
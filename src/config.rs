@@ -0,0 +1,391 @@
+// config.rs - layered configuration: built-in defaults < /etc/raur.conf < user config
+// < environment < CLI flags
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::paths;
+
+pub const SYSTEM_CONFIG: &str = "/etc/raur.conf";
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Aur,
+    Github,
+}
+
+// Where package metadata actually comes from, as reported in JSON output
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    Aur,
+    Github,
+    // the index downloaded by sync-index (--offline)
+    Offline,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+// How build files are shown before building
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewPolicy {
+    // show the diff since the last reviewed revision (full files the first time)
+    Diff,
+    // always show the full PKGBUILD and install scripts
+    Full,
+    // don't show anything
+    Skip,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoveMakeDeps {
+    Ask,
+    Always,
+    Never,
+}
+
+// How questions are answered: on the terminal, or up front with --yes, --no or --noconfirm
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Answer {
+    #[default]
+    Ask,
+    Yes,
+    No,
+    // the configured default of each question
+    Default,
+}
+
+// Servers raur talks to; all of them can point at a self-hosted AUR or a local mock
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Endpoints {
+    // RPC base URL, queries are built by aur_rpc_url
+    pub aur_rpc: String,
+    // packages are cloned from <aur_git>/<pkgbase>.git
+    pub aur_git: String,
+    // git repo with one branch per package base
+    pub mirror_git: String,
+    // raw files are fetched from <mirror_raw>/<pkgbase>/<file>
+    pub mirror_raw: String,
+    // metadata dump used by sync-index
+    pub aur_meta: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints {
+            aur_rpc: "https://aur.archlinux.org/rpc".to_string(),
+            aur_git: "https://aur.archlinux.org".to_string(),
+            mirror_git: "https://github.com/archlinux/aur.git".to_string(),
+            mirror_raw: "https://raw.githubusercontent.com/archlinux/aur".to_string(),
+            aur_meta: "https://aur.archlinux.org/packages-meta-ext-v1.json.gz".to_string(),
+        }
+    }
+}
+
+// Environment variables overriding the endpoints, checked after the config files
+pub const ENDPOINT_ENV: &[(&str, &str)] = &[
+    ("aur-rpc", "RAUR_AUR_RPC"),
+    ("aur-git", "RAUR_AUR_GIT"),
+    ("mirror-git", "RAUR_MIRROR_GIT"),
+    ("mirror-raw", "RAUR_MIRROR_RAW"),
+    ("aur-meta", "RAUR_AUR_META"),
+];
+
+impl Endpoints {
+    // Set an endpoint by its command line name ("aur-rpc", ...)
+    pub fn set(&mut self, name: &str, value: String) {
+        match name {
+            "aur-rpc" => {
+                self.aur_rpc = value;
+            }
+            "aur-git" => {
+                self.aur_git = value;
+            }
+            "mirror-git" => {
+                self.mirror_git = value;
+            }
+            "mirror-raw" => {
+                self.mirror_raw = value;
+            }
+            "aur-meta" => {
+                self.aur_meta = value;
+            }
+            _ => unreachable!("unknown endpoint {}", name),
+        }
+    }
+
+    // RPC request for `query` ("type=info&arg[]=...") against version 5 of the interface
+    pub fn aur_rpc_url(&self, query: &str) -> String {
+        format!("{}/?v=5&{}", self.aur_rpc.trim_end_matches('/'), query)
+    }
+
+    pub fn aur_git_url(&self, base: &str) -> String {
+        format!("{}/{}.git", self.aur_git.trim_end_matches('/'), base)
+    }
+
+    pub fn mirror_raw_url(&self, base: &str, file: &str) -> String {
+        format!("{}/{}/{}", self.mirror_raw.trim_end_matches('/'), base, file)
+    }
+}
+
+// Local pacman repository built packages are added to and installed from
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LocalRepo {
+    // repository name, as the [section] in pacman.conf
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+// Answer used when a question is confirmed with Enter
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptDefaults {
+    pub proceed: bool,
+    pub review: bool,
+    pub remove_make_deps: bool,
+    pub uninstall: bool,
+    pub import_keys: bool,
+}
+
+impl Default for PromptDefaults {
+    fn default() -> Self {
+        PromptDefaults {
+            proceed: true,
+            review: true,
+            remove_make_deps: true,
+            uninstall: true,
+            import_keys: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub backend: Backend,
+    pub format: OutputFormat,
+    // concurrent network requests during update checks
+    pub jobs: usize,
+    // answer search, info and dependency lookups from the index made by sync-index
+    pub offline: bool,
+    // set by --yes, --no and --noconfirm
+    #[serde(skip)]
+    pub answer: Answer,
+    // only print the commands that would change the system (command line only)
+    #[serde(skip)]
+    pub dry_run: bool,
+    // built archives kept per package for rollback and downgrade (0 keeps none)
+    pub keep_archives: usize,
+    // defaults to <cache dir>/pkg
+    pub archive_dir: Option<PathBuf>,
+    // install through a local repository instead of pacman -U
+    pub local_repo: Option<LocalRepo>,
+    // build with makechrootpkg in a devtools chroot
+    pub chroot: bool,
+    // defaults to <cache dir>/chroot
+    pub chroot_dir: Option<PathBuf>,
+    // build everything before installing anything
+    pub transaction: bool,
+    // check VCS packages for new upstream commits on update
+    pub devel: bool,
+    pub build_dir: Option<PathBuf>,
+    pub review: ReviewPolicy,
+    pub remove_make_deps: RemoveMakeDeps,
+    // never checked by update
+    pub ignore: Vec<String>,
+    // checked, but reported as held instead of updated
+    pub hold: Vec<String>,
+    // IgnorePkg and IgnoreGroup in here hold packages too
+    pub pacman_conf: PathBuf,
+    // keyserver missing validpgpkeys are received from; gpg's default if unset
+    pub keyserver: Option<String>,
+    // key file imported instead of asking a keyserver
+    pub keyring: Option<PathBuf>,
+    // dependency -> AUR package picked to provide it, written when a provider is chosen
+    pub providers: BTreeMap<String, String>,
+    pub endpoints: Endpoints,
+    pub prompts: PromptDefaults,
+    // config files that were found and applied, lowest priority first
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            backend: Backend::Aur,
+            format: OutputFormat::Text,
+            jobs: 8,
+            offline: false,
+            answer: Answer::Ask,
+            dry_run: false,
+            keep_archives: 3,
+            archive_dir: None,
+            local_repo: None,
+            chroot: false,
+            chroot_dir: None,
+            transaction: false,
+            devel: false,
+            build_dir: None,
+            review: ReviewPolicy::Diff,
+            remove_make_deps: RemoveMakeDeps::Ask,
+            ignore: Vec::new(),
+            hold: Vec::new(),
+            pacman_conf: PathBuf::from("/etc/pacman.conf"),
+            keyserver: None,
+            keyring: None,
+            providers: BTreeMap::new(),
+            endpoints: Endpoints::default(),
+            prompts: PromptDefaults::default(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+pub fn user_config_path() -> PathBuf {
+    paths::config_dir().join("config.toml")
+}
+
+// Remember `pkg` as the provider of `dep` in the user config. The file is edited in place, so
+// comments and formatting are kept.
+pub fn save_provider(dep: &str, pkg: &str) -> Result<(), Box<dyn Error>> {
+    let path = user_config_path();
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(format!("cannot read {}: {}", path.display(), e).into());
+        }
+    };
+    let mut doc = text
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| format!("invalid config {}: {}", path.display(), e))?;
+    let providers = doc
+        .entry("providers")
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| format!("'providers' in {} is not a table", path.display()))?;
+    providers.insert(dep, toml_edit::value(pkg));
+    fs::create_dir_all(paths::config_dir())?;
+    fs::write(&path, doc.to_string())?;
+    Ok(())
+}
+
+// Merge `over` into `base`: tables are merged key by key, anything else is replaced
+fn merge(base: &mut toml::Value, over: toml::Value) {
+    match (base, over) {
+        (toml::Value::Table(base), toml::Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => {
+            *base = over;
+        }
+    }
+}
+
+fn read_layer(path: &Path) -> Result<Option<toml::Value>, Box<dyn Error>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(None);
+        }
+        Err(e) => {
+            return Err(format!("cannot read {}: {}", path.display(), e).into());
+        }
+    };
+    let value = text
+        .parse::<toml::Table>()
+        .map_err(|e| format!("invalid config {}: {}", path.display(), e))?;
+    Ok(Some(toml::Value::Table(value)))
+}
+
+impl Config {
+    // Load /etc/raur.conf, the user config on top of it and then the RAUR_* endpoint variables.
+    // Missing files are fine.
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        let mut merged = toml::Value::Table(toml::Table::new());
+        let mut sources = Vec::new();
+        for path in [PathBuf::from(SYSTEM_CONFIG), user_config_path()] {
+            if let Some(layer) = read_layer(&path)? {
+                merge(&mut merged, layer);
+                sources.push(path);
+            }
+        }
+        let mut config: Config = merged
+            .try_into()
+            .map_err(|e| format!("invalid configuration: {}", e))?;
+        config.sources = sources;
+        for (name, var) in ENDPOINT_ENV {
+            if let Ok(value) = std::env::var(var) {
+                config.endpoints.set(name, value);
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn use_github(&self) -> bool {
+        self.backend == Backend::Github
+    }
+
+    // Source of search and info results: the offline index replaces both the RPC and the mirror
+    pub fn data_source(&self) -> DataSource {
+        if self.offline {
+            DataSource::Offline
+        } else if self.use_github() {
+            DataSource::Github
+        } else {
+            DataSource::Aur
+        }
+    }
+
+    // Directory holding one clone per package base
+    pub fn build_root(&self) -> PathBuf {
+        match &self.build_dir {
+            Some(dir) => paths::expand_home(dir),
+            None => paths::cache_dir().join("build"),
+        }
+    }
+
+    pub fn build_dir(&self, base: &str) -> PathBuf {
+        self.build_root().join(base)
+    }
+
+    // Directory the built archives are kept in
+    pub fn archive_dir(&self) -> PathBuf {
+        match &self.archive_dir {
+            Some(dir) => paths::expand_home(dir),
+            None => paths::cache_dir().join("pkg"),
+        }
+    }
+
+    // Directory holding the devtools chroot ("root" plus per-user working copies)
+    pub fn chroot_dir(&self) -> PathBuf {
+        match &self.chroot_dir {
+            Some(dir) => paths::expand_home(dir),
+            None => paths::cache_dir().join("chroot"),
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
@@ -0,0 +1,688 @@
+// deps.rs - recursive dependency resolution for AUR packages
+use std::cmp::Ordering;
+use std::collections::{ HashMap, HashSet };
+use std::error::Error;
+use std::process::{ Command as Shell, Stdio };
+
+use crate::config::{ self, Config };
+use crate::prompt;
+use crate::vercmp::vercmp;
+use crate::{ AurPkg, fetch_info_many, fetch_providers };
+
+// One clone + makepkg run: a package base and the sub-packages of it we need installed
+pub struct BuildUnit {
+    pub base: String,
+    pub pkgs: Vec<AurPkg>,
+    // package bases of this build that must be installed before this one can be built
+    pub deps: Vec<String>,
+    // the part of `deps` this one also needs at run time (from depends)
+    pub runtime_deps: Vec<String>,
+}
+
+// Build plan for the requested packages.
+// `build` is ordered so that every package base comes after the bases it depends on.
+pub struct Resolution {
+    pub build: Vec<BuildUnit>,
+    pub explicit: HashSet<String>,
+    pub repo_deps: Vec<String>,
+    pub missing: Vec<String>,
+    // conflicts between the packages to build and installed packages or each other
+    pub conflicts: Vec<String>,
+}
+
+impl Resolution {
+    pub fn is_explicit(&self, name: &str) -> bool {
+        self.explicit.contains(name)
+    }
+
+    // Units of the plan that must be installed to build `unit`: its direct dependencies and
+    // whatever those need at run time
+    pub fn dep_closure(&self, unit: &BuildUnit) -> Vec<&BuildUnit> {
+        self.runtime_closure(unit.deps.iter())
+    }
+
+    // True if `unit` is requested or needed at run time by a requested package, as opposed to
+    // only being needed to build one
+    pub fn needed_at_runtime(&self, unit: &BuildUnit) -> bool {
+        let requested = self.build
+            .iter()
+            .filter(|u| u.pkgs.iter().any(|p| self.is_explicit(&p.name)))
+            .map(|u| &u.base);
+        self.runtime_closure(requested).iter().any(|u| u.base == unit.base)
+    }
+
+    // The units of `bases` and, transitively, those they need at run time
+    fn runtime_closure<'a>(&self, bases: impl Iterator<Item = &'a String>) -> Vec<&BuildUnit> {
+        let mut closure: Vec<&BuildUnit> = Vec::new();
+        let mut pending: Vec<&String> = bases.collect();
+        while let Some(base) = pending.pop() {
+            if closure.iter().any(|u| &u.base == base) {
+                continue;
+            }
+            if let Some(dep) = self.build.iter().find(|u| &u.base == base) {
+                pending.extend(&dep.runtime_deps);
+                closure.push(dep);
+            }
+        }
+        closure
+    }
+}
+
+// Strip a version constraint from a dependency string ("foo>=1.2" -> "foo")
+pub fn dep_name(dep: &str) -> &str {
+    match dep.find(['<', '>', '=']) {
+        Some(idx) => &dep[..idx],
+        None => dep,
+    }
+}
+
+// Split a dependency into its name and version constraint ("foo>=1.2" -> ("foo", (">=", "1.2")))
+fn parse_dep(dep: &str) -> (&str, Option<(&str, &str)>) {
+    let name = dep_name(dep);
+    let rest = &dep[name.len()..];
+    if rest.is_empty() {
+        return (name, None);
+    }
+    let op_len = rest
+        .bytes()
+        .take_while(|b| b"<>=".contains(b))
+        .count();
+    (name, Some((&rest[..op_len], &rest[op_len..])))
+}
+
+fn version_matches(version: &str, op: &str, wanted: &str) -> bool {
+    let ord = vercmp(version, wanted);
+    match op {
+        "=" => ord == Ordering::Equal,
+        ">=" => ord != Ordering::Less,
+        "<=" => ord != Ordering::Greater,
+        ">" => ord == Ordering::Greater,
+        "<" => ord == Ordering::Less,
+        _ => false,
+    }
+}
+
+// True if package `name` at `version` with `provides` satisfies `dep`, the way pacman decides it:
+// a versioned dependency is only satisfied by a provide if the provide has a version too
+pub fn satisfies(dep: &str, name: &str, version: Option<&str>, provides: &[String]) -> bool {
+    let (wanted, constraint) = parse_dep(dep);
+    let matches = |have: &str, have_version: Option<&str>| {
+        have == wanted &&
+            (match (constraint, have_version) {
+                (None, _) => true,
+                (Some((op, ver)), Some(have_version)) => version_matches(have_version, op, ver),
+                (Some(_), None) => false,
+            })
+    };
+    matches(name, version) ||
+        provides.iter().any(|provide| {
+            let (provided, provided_version) = parse_dep(provide);
+            matches(provided, provided_version.map(|(_, v)| v))
+        })
+}
+
+fn pkg_satisfies(pkg: &AurPkg, dep: &str) -> bool {
+    satisfies(dep, &pkg.name, pkg.version.as_deref(), &pkg.provides)
+}
+
+// The candidates that satisfy `dep`, in menu order: the package of that name first, then the
+// most voted
+fn satisfying(dep: &str, mut candidates: Vec<AurPkg>) -> Vec<AurPkg> {
+    let name = dep_name(dep);
+    candidates.retain(|c| pkg_satisfies(c, dep));
+    candidates.sort_by(|a, b| {
+        (b.name == name)
+            .cmp(&(a.name == name))
+            .then(b.num_votes.unwrap_or(0).cmp(&a.num_votes.unwrap_or(0)))
+    });
+    candidates
+}
+
+// An installed package, as far as conflicts are concerned
+#[derive(Default)]
+struct Installed {
+    name: String,
+    version: String,
+    provides: Vec<String>,
+    conflicts: Vec<String>,
+}
+
+// Every installed package from `pacman -Qi`; empty if pacman can't be asked
+fn installed_packages() -> Vec<Installed> {
+    let Ok(output) = Shell::new("pacman").arg("-Qi").env("LC_ALL", "C").output() else {
+        return Vec::new();
+    };
+    let mut pkgs: Vec<Installed> = Vec::new();
+    // "Key             : value"; lists are separated by spaces and empty ones read "None"
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once(" : ") else {
+            continue;
+        };
+        let value = value.trim();
+        let list = || -> Vec<String> {
+            if value == "None" { Vec::new() } else { value.split_whitespace().map(String::from).collect() }
+        };
+        if key.trim() == "Name" {
+            pkgs.push(Installed { name: value.to_string(), ..Installed::default() });
+            continue;
+        }
+        let Some(pkg) = pkgs.last_mut() else {
+            continue;
+        };
+        match key.trim() {
+            "Version" => {
+                pkg.version = value.to_string();
+            }
+            "Provides" => {
+                pkg.provides = list();
+            }
+            "Conflicts With" => {
+                pkg.conflicts = list();
+            }
+            _ => {}
+        }
+    }
+    pkgs
+}
+
+// Describe the conflicts pacman would stop the install with. Installed packages that are rebuilt
+// under the same name are upgraded, not in conflict.
+fn find_conflicts(build: &[BuildUnit]) -> Vec<String> {
+    let planned: Vec<&AurPkg> = build
+        .iter()
+        .flat_map(|u| &u.pkgs)
+        .collect();
+    let installed: Vec<Installed> = installed_packages()
+        .into_iter()
+        .filter(|i| !planned.iter().any(|p| p.name == i.name))
+        .collect();
+
+    let mut found: Vec<String> = Vec::new();
+    let mut report = |msg: String| {
+        if !found.contains(&msg) {
+            found.push(msg);
+        }
+    };
+    for pkg in &planned {
+        for dep in &pkg.conflicts {
+            for other in planned.iter().filter(|o| o.name != pkg.name) {
+                if pkg_satisfies(other, dep) {
+                    report(format!("{} conflicts with {}", pkg.name, other.name));
+                }
+            }
+            for inst in &installed {
+                if satisfies(dep, &inst.name, Some(&inst.version), &inst.provides) {
+                    report(format!("{} conflicts with installed {}", pkg.name, inst.name));
+                }
+            }
+        }
+        for inst in &installed {
+            if inst.conflicts.iter().any(|dep| pkg_satisfies(pkg, dep)) {
+                report(format!("installed {} conflicts with {}", inst.name, pkg.name));
+            }
+        }
+    }
+    found
+}
+
+// Ask pacman which of `deps` are not satisfied by installed packages.
+// `pacman -T` understands version constraints and provides, and prints the unsatisfied ones.
+fn unsatisfied(deps: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    if deps.is_empty() {
+        return Ok(Vec::new());
+    }
+    let output = Shell::new("pacman").arg("-T").args(deps).output()?;
+    let missing = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    Ok(missing)
+}
+
+// Return true if the dependency can be installed from the official repositories. pacman -S
+// accepts versioned dependencies and picks a package providing them if none has that name.
+fn in_repos(dep: &str) -> bool {
+    Shell::new("pacman")
+        .args(["-Sp", "--print-format", "%n", "--noconfirm", dep])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+// Dependency graph of AUR packages. Edges point from a package to the AUR dependencies it needs,
+// as dependency strings until fetch_all maps them to the packages that satisfy them.
+struct Resolver<'a> {
+    cfg: &'a Config,
+    nodes: HashMap<String, AurPkg>,
+    edges: HashMap<String, Vec<String>>,
+    // the edges that come from depends rather than makedepends/checkdepends
+    runtime: HashMap<String, Vec<String>>,
+    explicit: HashSet<String>,
+    repo_deps: Vec<String>,
+    missing: Vec<String>,
+    // every AUR package name already fetched or waiting to be fetched
+    known: HashSet<String>,
+}
+
+impl Resolver<'_> {
+    // Record `pkg` in the graph and return the AUR dependencies that still need fetching, as
+    // dependency strings
+    fn add(&mut self, pkg: AurPkg) -> Result<Vec<String>, Box<dyn Error>> {
+        let all_deps: Vec<String> = pkg.depends
+            .iter()
+            .chain(pkg.make_depends.iter())
+            .chain(pkg.check_depends.iter())
+            .cloned()
+            .collect();
+
+        // requested packages are always built by us, even if something already satisfies them
+        let (requested, others): (Vec<String>, Vec<String>) = all_deps
+            .into_iter()
+            .partition(|d| self.explicit.contains(dep_name(d)));
+        let mut aur_deps: Vec<String> = requested;
+
+        // a chroot only has base-devel, so whatever the host has installed doesn't count there
+        let needed = if self.cfg.chroot { others } else { unsatisfied(&others)? };
+        for dep in needed {
+            if self.repo_deps.contains(&dep) || aur_deps.contains(&dep) {
+                continue;
+            }
+            // packages already in the plan win over the repos, also when they only provide `dep`
+            let planned = self.known.contains(dep_name(&dep)) || self.provider(&dep).is_some();
+            if !planned && in_repos(&dep) {
+                self.repo_deps.push(dep);
+                continue;
+            }
+            aur_deps.push(dep);
+        }
+
+        let unprovided: Vec<String> = aur_deps
+            .iter()
+            .filter(|d| self.provider(d).is_none())
+            .cloned()
+            .collect();
+        let new: Vec<String> = unprovided
+            .into_iter()
+            .filter(|dep| self.known.insert(dep_name(dep).to_string()))
+            .collect();
+        self.edges.insert(pkg.name.clone(), aur_deps);
+        self.nodes.insert(pkg.name.clone(), pkg);
+        Ok(new)
+    }
+
+    // Fetch the graph breadth-first so each dependency level costs a single batched RPC round.
+    // The queue holds dependency strings, so candidates can be checked against their versions.
+    fn fetch_all(&mut self, names: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut queue: Vec<String> = names
+            .iter()
+            .filter(|n| self.known.insert(n.to_string()))
+            .cloned()
+            .collect();
+        // versions of the AUR packages named like a dependency, for reporting unsatisfied ones
+        let mut aur_versions: HashMap<String, String> = HashMap::new();
+        while !queue.is_empty() {
+            let queued: Vec<String> = queue
+                .iter()
+                .map(|dep| dep_name(dep).to_string())
+                .collect();
+            let mut found = fetch_info_many(self.cfg, &queued)?;
+            // dependencies may also be provided by AUR packages of another name; requested
+            // packages are taken by name
+            let searched: Vec<&String> = queued
+                .iter()
+                .filter(|n| !self.explicit.contains(*n))
+                .collect();
+            let mut providers = fetch_providers(self.cfg, &searched)?;
+            // search results lack depends and provides, so providers are fetched again in full
+            let mut partial: Vec<String> = providers
+                .iter()
+                .flatten()
+                .filter(|p| !found.iter().any(|f| f.name == p.name))
+                .map(|p| p.name.clone())
+                .collect();
+            partial.sort();
+            partial.dedup();
+            if !partial.is_empty() {
+                found.extend(fetch_info_many(self.cfg, &partial)?);
+            }
+
+            let mut next = Vec::new();
+            for (dep, name) in queue.iter().zip(&queued) {
+                let mut candidates: Vec<AurPkg> = found
+                    .iter()
+                    .filter(|p| &p.name == name)
+                    .cloned()
+                    .collect();
+                if let Some(pkg) = candidates.first() {
+                    aur_versions.insert(name.clone(), pkg.version.clone().unwrap_or_default());
+                }
+                if let Some(i) = searched.iter().position(|s| *s == name) {
+                    for provider in std::mem::take(&mut providers[i]) {
+                        if candidates.iter().any(|c| c.name == provider.name) {
+                            continue;
+                        }
+                        if let Some(pkg) = found.iter().find(|f| f.name == provider.name) {
+                            candidates.push(pkg.clone());
+                        }
+                    }
+                }
+                // unsatisfied dependencies are reported once the whole graph is known
+                let Some(pkg) = self.pick(dep, candidates)? else {
+                    continue;
+                };
+                // a provider that is already part of the plan needs no second visit
+                if pkg.name != *name && !self.known.insert(pkg.name.clone()) {
+                    continue;
+                }
+                next.extend(self.add(pkg)?);
+            }
+            queue = next;
+        }
+
+        for name in names.iter().filter(|n| !self.nodes.contains_key(*n)) {
+            self.missing.push(name.clone());
+        }
+        // point edges at the packages satisfying them; a dependency nothing in the plan
+        // satisfies is missing, and makepkg would only fail on it mid-build
+        let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, deps) in &self.edges {
+            let mut targets: Vec<String> = Vec::new();
+            for dep in deps {
+                match self.provider(dep) {
+                    Some(provider) => {
+                        if !targets.iter().any(|t| t == provider) {
+                            targets.push(provider.to_string());
+                        }
+                    }
+                    None => {
+                        let msg = match aur_versions.get(dep_name(dep)) {
+                            Some(version) if dep_name(dep) != dep => {
+                                format!("{} (the AUR has {})", dep, version)
+                            }
+                            _ => dep.clone(),
+                        };
+                        if !self.missing.contains(&msg) {
+                            self.missing.push(msg);
+                        }
+                    }
+                }
+            }
+            edges.insert(name.clone(), targets);
+        }
+        self.edges = edges;
+
+        for (name, pkg) in &self.nodes {
+            let targets = self.edges.get(name).cloned().unwrap_or_default();
+            let runtime: Vec<String> = targets
+                .into_iter()
+                .filter(|target| pkg.depends.iter().any(|d| self.provider(d) == Some(target)))
+                .collect();
+            self.runtime.insert(name.clone(), runtime);
+        }
+        Ok(())
+    }
+
+    // Pick the package to build for `dep` among the AUR packages called or providing it that
+    // satisfy its version: the remembered choice if there is one, otherwise the user's pick
+    // from a menu
+    fn pick(&self, dep: &str, candidates: Vec<AurPkg>) -> Result<Option<AurPkg>, Box<dyn Error>> {
+        let name = dep_name(dep);
+        let mut candidates = satisfying(dep, candidates);
+        if candidates.len() <= 1 {
+            return Ok(candidates.pop());
+        }
+        if let Some(choice) = self.cfg.providers.get(name) &&
+            let Some(i) = candidates.iter().position(|c| &c.name == choice)
+        {
+            return Ok(Some(candidates.swap_remove(i)));
+        }
+
+        let options: Vec<String> = candidates
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} {} (votes: {}, popularity: {:.2})",
+                    p.name,
+                    p.version.as_deref().unwrap_or(""),
+                    p.num_votes.unwrap_or(0),
+                    p.popularity.unwrap_or(0.0)
+                )
+            })
+            .collect();
+        println!("\nThere are {} AUR packages that provide {}:", candidates.len(), dep);
+        let i = prompt::choose(self.cfg, "Which one?", &options, 0)?;
+        let pkg = candidates.swap_remove(i);
+
+        // only an actual answer is remembered, not a default picked by --yes or a dry run
+        if !prompt::is_automatic(self.cfg) && !self.cfg.dry_run {
+            match config::save_provider(name, &pkg.name) {
+                Ok(()) => println!("Remembered {} as the provider of {}", pkg.name, name),
+                Err(e) => eprintln!("Cannot remember the provider of {}: {}", name, e),
+            }
+        }
+        Ok(Some(pkg))
+    }
+
+    // Package of the graph that satisfies `dep`: the one of that name if its version fits,
+    // otherwise the first (by name) that provides it
+    fn provider(&self, dep: &str) -> Option<&str> {
+        if let Some(pkg) = self.nodes.get(dep_name(dep)) && pkg_satisfies(pkg, dep) {
+            return Some(&pkg.name);
+        }
+        self.nodes
+            .values()
+            .filter(|pkg| pkg_satisfies(pkg, dep))
+            .map(|pkg| pkg.name.as_str())
+            .min()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Visiting,
+    Done,
+}
+
+// Depth-first topological sort: dependencies are emitted before their dependents.
+// On a cycle, returns the offending chain (e.g. ["a", "b", "a"]).
+fn topo_sort(
+    roots: &[String],
+    edges: &HashMap<String, Vec<String>>
+) -> Result<Vec<String>, Vec<String>> {
+    fn walk(
+        node: &str,
+        edges: &HashMap<String, Vec<String>>,
+        marks: &mut HashMap<String, Mark>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>
+    ) -> Result<(), Vec<String>> {
+        match marks.get(node) {
+            Some(Mark::Done) => {
+                return Ok(());
+            }
+            Some(Mark::Visiting) => {
+                let start = path
+                    .iter()
+                    .position(|p| p == node)
+                    .unwrap_or(0);
+                let mut chain = path[start..].to_vec();
+                chain.push(node.to_string());
+                return Err(chain);
+            }
+            None => {}
+        }
+
+        marks.insert(node.to_string(), Mark::Visiting);
+        path.push(node.to_string());
+        for dep in edges.get(node).into_iter().flatten() {
+            walk(dep, edges, marks, path, order)?;
+        }
+        path.pop();
+        marks.insert(node.to_string(), Mark::Done);
+        order.push(node.to_string());
+        Ok(())
+    }
+
+    let mut marks = HashMap::new();
+    let mut path = Vec::new();
+    let mut order = Vec::new();
+    for root in roots {
+        walk(root, edges, &mut marks, &mut path, &mut order)?;
+    }
+    Ok(order)
+}
+
+// Resolve the requested AUR packages and all of their AUR dependencies (depends, makedepends and
+// checkdepends, satisfied by name or by provides) into a build plan. Dependencies available in the
+// official repositories are left to makepkg -s. Fails if the AUR dependencies form a cycle.
+pub fn resolve(cfg: &Config, names: &[String]) -> Result<Resolution, Box<dyn Error>> {
+    let mut resolver = Resolver {
+        cfg,
+        nodes: HashMap::new(),
+        edges: HashMap::new(),
+        runtime: HashMap::new(),
+        explicit: names.iter().cloned().collect(),
+        repo_deps: Vec::new(),
+        missing: Vec::new(),
+        known: HashSet::new(),
+    };
+    resolver.fetch_all(names)?;
+
+    // split packages are built once per package base, so the graph is ordered by base
+    let base_of = |name: &String| -> String {
+        resolver.nodes
+            .get(name)
+            .map(|p| p.base().to_string())
+            .unwrap_or_else(|| name.clone())
+    };
+    let by_base = |edges: &HashMap<String, Vec<String>>| {
+        let mut base_edges: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, deps) in edges {
+            let base = base_of(name);
+            let entry = base_edges.entry(base.clone()).or_default();
+            for dep in deps {
+                let dep_base = base_of(dep);
+                if dep_base != base && !entry.contains(&dep_base) {
+                    entry.push(dep_base);
+                }
+            }
+        }
+        base_edges
+    };
+    let mut base_edges = by_base(&resolver.edges);
+    let mut runtime_edges = by_base(&resolver.runtime);
+
+    let mut roots: Vec<String> = Vec::new();
+    for name in names.iter().filter(|n| resolver.nodes.contains_key(*n)) {
+        let base = base_of(name);
+        if !roots.contains(&base) {
+            roots.push(base);
+        }
+    }
+    let order = topo_sort(&roots, &base_edges).map_err(|chain| {
+        format!("dependency cycle detected: {}", chain.join(" -> "))
+    })?;
+
+    let mut members: HashMap<String, Vec<AurPkg>> = HashMap::new();
+    for (_, pkg) in resolver.nodes.drain() {
+        members.entry(pkg.base().to_string()).or_default().push(pkg);
+    }
+    let build = order
+        .into_iter()
+        .filter_map(|base| {
+            let mut pkgs = members.remove(&base)?;
+            pkgs.sort_by(|a, b| a.name.cmp(&b.name));
+            let deps = base_edges.remove(&base).unwrap_or_default();
+            let runtime_deps = runtime_edges.remove(&base).unwrap_or_default();
+            Some(BuildUnit { base, pkgs, deps, runtime_deps })
+        })
+        .collect::<Vec<_>>();
+    Ok(Resolution {
+        conflicts: find_conflicts(&build),
+        build,
+        explicit: resolver.explicit,
+        repo_deps: resolver.repo_deps,
+        missing: resolver.missing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(node, deps)| {
+                (node.to_string(), deps.iter().map(|d| d.to_string()).collect())
+            })
+            .collect()
+    }
+
+    fn roots(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let edges = graph(&[("app", &["lib", "tool"]), ("lib", &["base"]), ("tool", &["base"])]);
+        let order = topo_sort(&roots(&["app"]), &edges).unwrap();
+        assert_eq!(order, ["base", "lib", "tool", "app"]);
+    }
+
+    #[test]
+    fn shared_dependencies_are_emitted_once() {
+        let edges = graph(&[("a", &["c"]), ("b", &["c"])]);
+        let order = topo_sort(&roots(&["a", "b"]), &edges).unwrap();
+        assert_eq!(order, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn cycles_report_the_chain() {
+        let edges = graph(&[("top", &["a"]), ("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+        let chain = topo_sort(&roots(&["top"]), &edges).unwrap_err();
+        assert_eq!(chain, ["a", "b", "c", "a"]);
+
+        let edges = graph(&[("a", &["a"])]);
+        assert_eq!(topo_sort(&roots(&["a"]), &edges).unwrap_err(), ["a", "a"]);
+    }
+
+    fn aur(name: &str, version: &str, provides: &[&str], votes: u64) -> AurPkg {
+        serde_json::from_value(
+            serde_json::json!({
+                "Name": name,
+                "Version": version,
+                "Provides": provides,
+                "NumVotes": votes,
+            })
+        ).unwrap()
+    }
+
+    fn names(pkgs: &[AurPkg]) -> Vec<&str> {
+        pkgs.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn candidates_must_satisfy_the_version() {
+        let candidates = vec![
+            aur("bar", "1.0-1", &[], 50),
+            aur("bar-git", "r10.abc-1", &["bar"], 5),
+            aur("bar-bin", "2.1-1", &["bar=2.1"], 1),
+            aur("bar-legacy", "1.5-1", &["bar=1.5"], 9)
+        ];
+        let picked = satisfying("bar>=2", candidates.clone());
+        assert_eq!(names(&picked), ["bar-bin"]);
+        // the package of that name first, then by votes
+        let picked = satisfying("bar", candidates);
+        assert_eq!(names(&picked), ["bar", "bar-legacy", "bar-git", "bar-bin"]);
+    }
+
+    #[test]
+    fn unrelated_candidates_are_dropped() {
+        let picked = satisfying("java-runtime", vec![aur("jdk-bin", "21-1", &[], 3)]);
+        assert!(picked.is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+// devel.rs - update detection for VCS (-git/-svn/-hg) packages
+// The revision of every VCS source that a package was built from is recorded after the build;
+// `update --devel` asks the upstream repositories for their current revision and compares.
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process::{ Command as Shell, Stdio };
+
+use crate::config::Config;
+use crate::paths::state_dir;
+use crate::pool;
+use crate::srcinfo::SrcInfo;
+
+// A source= entry that follows a moving branch of a VCS repository
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VcsSource {
+    pub protocol: String,
+    pub url: String,
+    // branch to follow; None for the default branch
+    pub branch: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecordedSource {
+    #[serde(flatten)]
+    pub source: VcsSource,
+    // None if the built revision could not be read; such sources always count as changed
+    pub revision: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct DevelEntry {
+    pub pkgnames: Vec<String>,
+    pub sources: Vec<RecordedSource>,
+}
+
+// Recorded sources per package base
+#[derive(Serialize, Deserialize, Default)]
+pub struct DevelDb {
+    pub packages: BTreeMap<String, DevelEntry>,
+}
+
+fn db_path() -> PathBuf {
+    state_dir().join("devel.json")
+}
+
+impl DevelDb {
+    pub fn load() -> Result<DevelDb, Box<dyn Error>> {
+        match fs::read(db_path()) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DevelDb::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(state_dir())?;
+        fs::write(db_path(), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Package base an installed package was built from
+    fn base_of(&self, pkgname: &str) -> Option<&str> {
+        self.packages
+            .iter()
+            .find(|(_, entry)| entry.pkgnames.iter().any(|n| n == pkgname))
+            .map(|(base, _)| base.as_str())
+    }
+}
+
+// Parse a source= entry ("name::git+https://host/repo.git#branch=dev") into a VcsSource.
+// Sources pinned to a commit, tag or revision never change and are skipped.
+pub fn parse_source(source: &str) -> Option<VcsSource> {
+    let source = match source.find("::") {
+        Some(idx) => &source[idx + 2..],
+        None => source,
+    };
+    let (url, fragment) = match source.split_once('#') {
+        Some((url, fragment)) => (url, Some(fragment)),
+        None => (source, None),
+    };
+    // "?signed" and similar query options are for makepkg only
+    let url = url.split('?').next().unwrap_or(url);
+
+    let scheme = url.split("://").next()?;
+    let (protocol, url) = match scheme.split_once('+') {
+        Some((protocol, _)) => (protocol, &url[protocol.len() + 1..]),
+        None if scheme == "git" => ("git", url),
+        None => {
+            return None;
+        }
+    };
+    if !["git", "hg", "svn"].contains(&protocol) {
+        return None;
+    }
+
+    let mut branch = None;
+    if let Some(fragment) = fragment {
+        match fragment.split_once('=') {
+            Some(("branch", value)) => {
+                branch = Some(value.to_string());
+            }
+            // commit=, tag=, revision=
+            Some(_) => {
+                return None;
+            }
+            None => {}
+        }
+    }
+    Some(VcsSource { protocol: protocol.to_string(), url: url.to_string(), branch })
+}
+
+// Directory makepkg clones a source into, both in SRCDEST and in src/: the "name::" prefix, or
+// the last component of the URL (without ".git" for git)
+pub fn source_dir(source: &str) -> String {
+    if let Some((name, _)) = source.split_once("::") {
+        return name.to_string();
+    }
+    let url = source.split('#').next().unwrap_or(source);
+    let url = url.split('?').next().unwrap_or(url);
+    let name = url.trim_end_matches('/').rsplit('/').next().unwrap_or(url);
+    if source.starts_with("git") && let Some(idx) = name.find(".git") {
+        return name[..idx].to_string();
+    }
+    name.to_string()
+}
+
+fn command_output(cmd: &mut Shell) -> Option<String> {
+    let output = cmd.stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Current upstream revision of a VCS source, without cloning it
+pub fn remote_revision(source: &VcsSource) -> Option<String> {
+    let output = match source.protocol.as_str() {
+        "git" => {
+            let reference = match &source.branch {
+                Some(branch) => format!("refs/heads/{}", branch),
+                None => "HEAD".to_string(),
+            };
+            command_output(Shell::new("git").args(["ls-remote", &source.url, &reference]))?
+        }
+        "hg" => {
+            let mut cmd = Shell::new("hg");
+            cmd.args(["identify", "--id", &source.url]);
+            if let Some(branch) = &source.branch {
+                cmd.args(["-r", branch]);
+            }
+            command_output(&mut cmd)?
+        }
+        "svn" => {
+            command_output(Shell::new("svn").args(["info", "--show-item", "revision", &source.url]))?
+        }
+        _ => {
+            return None;
+        }
+    };
+    output.split_whitespace().next().map(|s| s.to_string())
+}
+
+// Revision of a source that the last build in `build_dir` used: that of the working copy in
+// src/, which makepkg leaves there until the next build, or else that of makepkg's own clone
+// (SRCDEST defaults to the build directory)
+fn built_revision(build_dir: &Path, dir: &str, source: &VcsSource) -> Option<String> {
+    let checkout = build_dir.join("src").join(dir);
+    let clone = build_dir.join(dir);
+    let output = match source.protocol.as_str() {
+        "git" if checkout.is_dir() => {
+            command_output(Shell::new("git").arg("-C").arg(&checkout).args(["rev-parse", "HEAD"]))?
+        }
+        "git" => {
+            let reference = match &source.branch {
+                Some(branch) => format!("refs/heads/{}", branch),
+                None => "HEAD".to_string(),
+            };
+            command_output(
+                Shell::new("git").arg("-C").arg(&clone).args(["rev-parse", "--verify", &reference])
+            )?
+        }
+        "hg" if checkout.is_dir() => {
+            command_output(Shell::new("hg").args(["identify", "--id", "-R"]).arg(&checkout))?
+        }
+        "hg" => {
+            let branch = source.branch.as_deref().unwrap_or("default");
+            command_output(
+                Shell::new("hg").args(["identify", "--id", "-r", branch, "-R"]).arg(&clone)
+            )?
+        }
+        "svn" => {
+            let dir = if checkout.is_dir() { checkout } else { clone };
+            command_output(Shell::new("svn").args(["info", "--show-item", "revision"]).arg(&dir))?
+        }
+        _ => {
+            return None;
+        }
+    };
+    output.split_whitespace().next().map(|s| s.to_string())
+}
+
+// VCS sources of a package base for the host architecture, with the directory makepkg clones
+// each into
+pub fn vcs_sources(srcinfo: &SrcInfo) -> Vec<(String, VcsSource)> {
+    let mut sources: Vec<(String, VcsSource)> = Vec::new();
+    for source in srcinfo.base_values("source") {
+        if let Some(vcs) = parse_source(&source) && !sources.iter().any(|(_, s)| *s == vcs) {
+            sources.push((source_dir(&source), vcs));
+        }
+    }
+    sources
+}
+
+// Record the revisions the VCS sources of a package base were just built from in `build_dir`
+pub fn record(
+    base: &str,
+    build_dir: &Path,
+    pkgnames: &[&str],
+    srcinfo: &SrcInfo
+) -> Result<(), Box<dyn Error>> {
+    let sources = vcs_sources(srcinfo);
+    if sources.is_empty() {
+        return Ok(());
+    }
+
+    let mut db = DevelDb::load()?;
+    let entry = db.packages.entry(base.to_string()).or_default();
+    for name in pkgnames {
+        if !entry.pkgnames.iter().any(|n| n == name) {
+            entry.pkgnames.push(name.to_string());
+        }
+    }
+    entry.sources = sources
+        .into_iter()
+        .map(|(dir, source)| {
+            let revision = built_revision(build_dir, &dir, &source);
+            if revision.is_none() {
+                eprintln!("Cannot tell which revision of {} {} was built", base, source.url);
+            }
+            RecordedSource { source, revision }
+        })
+        .collect();
+    db.save()
+}
+
+pub struct DevelCheck {
+    // installed packages whose upstream sources have new commits
+    pub outdated: Vec<String>,
+    // installed packages that look like VCS packages but were never recorded
+    pub untracked: Vec<String>,
+}
+
+// Check the recorded VCS sources of the installed packages `names` against upstream
+pub fn check(cfg: &Config, names: &[String]) -> Result<DevelCheck, Box<dyn Error>> {
+    let db = DevelDb::load()?;
+    let mut untracked = Vec::new();
+    let mut bases: Vec<&str> = Vec::new();
+    for name in names {
+        match db.base_of(name) {
+            Some(base) if !bases.contains(&base) => bases.push(base),
+            Some(_) => {}
+            None if is_vcs_name(name) => untracked.push(name.clone()),
+            None => {}
+        }
+    }
+
+    let recorded: Vec<(&str, &RecordedSource)> = bases
+        .iter()
+        .flat_map(|base| db.packages[*base].sources.iter().map(move |s| (*base, s)))
+        .collect();
+    let current = pool::map(&recorded, cfg.jobs, |(_, recorded)| remote_revision(&recorded.source));
+
+    let mut changed: Vec<&str> = Vec::new();
+    for ((base, recorded), revision) in recorded.iter().zip(current) {
+        match (revision, &recorded.revision) {
+            // nothing to compare with; a rebuild records it
+            (_, None) => changed.push(base),
+            (Some(rev), Some(built)) if rev != *built => changed.push(base),
+            (Some(_), Some(_)) => {}
+            (None, _) => {
+                let source = &recorded.source;
+                eprintln!("Cannot reach {} source {}", source.protocol, source.url);
+            }
+        }
+    }
+
+    let outdated = names
+        .iter()
+        .filter(|name| db.base_of(name).is_some_and(|base| changed.contains(&base)))
+        .cloned()
+        .collect();
+    Ok(DevelCheck { outdated, untracked })
+}
+
+// Only the protocols parse_source tracks; reinstalling a -bzr package would record nothing
+pub fn is_vcs_name(name: &str) -> bool {
+    ["-git", "-hg", "-svn"].iter().any(|s| name.ends_with(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vcs(protocol: &str, url: &str, branch: Option<&str>) -> Option<VcsSource> {
+        Some(VcsSource {
+            protocol: protocol.to_string(),
+            url: url.to_string(),
+            branch: branch.map(|b| b.to_string()),
+        })
+    }
+
+    #[test]
+    fn vcs_sources_are_parsed() {
+        assert_eq!(
+            parse_source("git+https://example.com/foo.git"),
+            vcs("git", "https://example.com/foo.git", None)
+        );
+        assert_eq!(
+            parse_source("foo::git+https://example.com/foo.git#branch=dev"),
+            vcs("git", "https://example.com/foo.git", Some("dev"))
+        );
+        assert_eq!(
+            parse_source("git://example.com/foo.git?signed"),
+            vcs("git", "git://example.com/foo.git", None)
+        );
+        assert_eq!(
+            parse_source("hg+https://example.com/repo#branch=stable"),
+            vcs("hg", "https://example.com/repo", Some("stable"))
+        );
+        assert_eq!(
+            parse_source("svn+svn://example.com/trunk"),
+            vcs("svn", "svn://example.com/trunk", None)
+        );
+    }
+
+    #[test]
+    fn pinned_and_plain_sources_are_skipped() {
+        for source in [
+            "git+https://example.com/foo.git#commit=abc123",
+            "git+https://example.com/foo.git#tag=v1.0",
+            "svn+https://example.com/trunk#revision=42",
+            "https://example.com/foo-1.0.tar.gz",
+            "foo.patch",
+            "bzr+https://example.com/foo",
+        ] {
+            assert_eq!(parse_source(source), None, "{}", source);
+        }
+    }
+
+    #[test]
+    fn clone_directories() {
+        assert_eq!(source_dir("git+https://example.com/foo.git"), "foo");
+        assert_eq!(source_dir("git+https://example.com/foo.git#branch=dev"), "foo");
+        assert_eq!(source_dir("bar::git+https://example.com/foo.git"), "bar");
+        assert_eq!(source_dir("hg+https://example.com/repo/"), "repo");
+        assert_eq!(source_dir("svn+https://example.com/proj.git/trunk"), "trunk");
+    }
+}
@@ -0,0 +1,146 @@
+// history.rs - append-only log of what raur installed, updated and removed
+// One JSON object per line in $XDG_STATE_HOME/raur/history.jsonl, oldest first.
+use serde::{ Deserialize, Serialize };
+use std::error::Error;
+use std::fs::{ self, OpenOptions };
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use crate::config::{ Backend, Config };
+use crate::paths::state_dir;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Install,
+    Update,
+    Downgrade,
+    Remove,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Success,
+    BuildFailed,
+    InstallFailed,
+    RemoveFailed,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Install => "install",
+            Action::Update => "update",
+            Action::Downgrade => "downgrade",
+            Action::Remove => "remove",
+        }
+    }
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::BuildFailed => "build-failed",
+            Outcome::InstallFailed => "install-failed",
+            Outcome::RemoveFailed => "remove-failed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    // seconds since the Unix epoch
+    pub time: u64,
+    pub action: Action,
+    pub package: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    pub backend: Option<Backend>,
+    // revision of the package's AUR git repo (or mirror branch) that was built
+    pub commit: Option<String>,
+    pub outcome: Outcome,
+}
+
+impl Entry {
+    pub fn new(action: Action, package: &str, outcome: Outcome) -> Entry {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Entry {
+            time,
+            action,
+            package: package.to_string(),
+            old_version: None,
+            new_version: None,
+            backend: None,
+            commit: None,
+            outcome,
+        }
+    }
+}
+
+fn history_path() -> PathBuf {
+    state_dir().join("history.jsonl")
+}
+
+// Append entries to the history. Nothing is written in a dry run; failing to write only warns,
+// since the transaction itself already happened.
+pub fn append(cfg: &Config, entries: &[Entry]) {
+    if cfg.dry_run || entries.is_empty() {
+        return;
+    }
+    let write = || -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(state_dir())?;
+        let mut file = OpenOptions::new().create(true).append(true).open(history_path())?;
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    };
+    if let Err(e) = write() {
+        eprintln!("Cannot write history to {}: {}", history_path().display(), e);
+    }
+}
+
+// All entries, oldest first. Lines that don't parse (e.g. a torn write) are skipped.
+pub fn load() -> Result<Vec<Entry>, Box<dyn Error>> {
+    let text = match fs::read_to_string(history_path()) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Vec::new());
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+    Ok(
+        text
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    )
+}
+
+// "2026-10-17 09:30" (UTC) for a Unix timestamp
+pub fn format_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let (hour, minute) = ((secs % 86400) / 3600, (secs % 3600) / 60);
+    // civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, hour, minute)
+}
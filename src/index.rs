@@ -0,0 +1,152 @@
+// index.rs - offline package metadata from the AUR's packages-meta-ext-v1.json.gz dump
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use reqwest::blocking::get;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{ Read, Write };
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+use std::time::{ Duration, SystemTime };
+
+use crate::AurPkg;
+use crate::config::Config;
+use crate::deps::dep_name;
+use crate::paths;
+
+pub const DUMP_NAME: &str = "packages-meta-ext-v1.json.gz";
+
+pub struct Index {
+    pkgs: Vec<AurPkg>,
+    by_name: HashMap<String, usize>,
+    pub age: Duration,
+}
+
+// The dump is kept gzip-compressed in the cache, the way the AUR serves it
+pub fn index_path() -> PathBuf {
+    paths::cache_dir().join(DUMP_NAME)
+}
+
+fn decode(data: &[u8]) -> Result<Vec<AurPkg>, Box<dyn Error>> {
+    // local files may already be decompressed
+    if data.starts_with(&[0x1f, 0x8b]) {
+        let mut json = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut json)?;
+        Ok(serde_json::from_slice(&json)?)
+    } else {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+// Human readable age like "3 hours" for the age indicator
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (n, unit) = match secs {
+        0..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" })
+}
+
+impl Index {
+    fn from_pkgs(pkgs: Vec<AurPkg>, age: Duration) -> Index {
+        let by_name = pkgs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.name.clone(), i))
+            .collect();
+        Index { pkgs, by_name, age }
+    }
+
+    fn read(path: &Path) -> Result<Index, Box<dyn Error>> {
+        let data = fs::read(path).map_err(|e| {
+            format!("cannot read offline index {} ({}); run 'raur sync-index' first", path.display(), e)
+        })?;
+        let age = fs::metadata(path)?
+            .modified()
+            .ok()
+            .and_then(|m| SystemTime::now().duration_since(m).ok())
+            .unwrap_or_default();
+        Ok(Index::from_pkgs(decode(&data)?, age))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pkgs.len()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AurPkg> {
+        self.by_name.get(name).map(|&i| &self.pkgs[i])
+    }
+
+    // Same matching as the RPC's default "name-desc" search
+    pub fn search(&self, term: &str) -> Vec<AurPkg> {
+        let term = term.to_lowercase();
+        self.pkgs
+            .iter()
+            .filter(|p| {
+                p.name.to_lowercase().contains(&term) ||
+                    p.description.as_deref().is_some_and(|d| d.to_lowercase().contains(&term))
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Packages called `name` or providing it, as the RPC's "provides" search finds them
+    pub fn providers(&self, name: &str) -> Vec<AurPkg> {
+        self.pkgs
+            .iter()
+            .filter(|p| p.name == name || p.provides.iter().any(|d| dep_name(d) == name))
+            .cloned()
+            .collect()
+    }
+}
+
+// The index loaded from the cache, read at most once per run
+pub fn load() -> Result<&'static Index, Box<dyn Error>> {
+    static INDEX: OnceLock<Result<Index, String>> = OnceLock::new();
+    let index = INDEX.get_or_init(|| {
+        let index = Index::read(&index_path()).map_err(|e| e.to_string())?;
+        eprintln!(
+            "Using offline index ({} packages, updated {} ago)",
+            index.len(),
+            format_age(index.age)
+        );
+        Ok(index)
+    });
+    index.as_ref().map_err(|e| e.clone().into())
+}
+
+// Download the metadata dump (or copy it from `file`) into the cache and check that it parses
+pub fn sync(cfg: &Config, file: Option<&Path>) -> Result<usize, Box<dyn Error>> {
+    let data = match file {
+        Some(path) => fs::read(path)?,
+        None => {
+            let resp = get(cfg.endpoints.aur_meta.as_str())?;
+            if !resp.status().is_success() {
+                return Err(
+                    format!("downloading {} failed: {}", cfg.endpoints.aur_meta, resp.status()).into()
+                );
+            }
+            resp.bytes()?.to_vec()
+        }
+    };
+    let count = decode(&data)?.len();
+    // a decompressed local file is compressed like a download, so the name stays true
+    let data = if data.starts_with(&[0x1f, 0x8b]) {
+        data
+    } else {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data)?;
+        encoder.finish()?
+    };
+
+    let path = index_path();
+    fs::create_dir_all(paths::cache_dir())?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, &data)?;
+    fs::rename(&tmp, &path)?;
+    Ok(count)
+}
@@ -1,33 +1,58 @@
 // main.rs - Simple AUR Helper
 use clap::{ Arg, ArgAction, Command };
 use reqwest::blocking::get;
-use serde::Deserialize;
+use serde::{ Deserialize, Serialize };
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
-use std::io::{ self, Write };
+use std::path::Path;
+use std::io;
 use std::process::{ exit, Command as Shell };
 extern crate nix;
 
 use nix::unistd::Uid;
 
-const AUR_RPC: &str = "https://aur.archlinux.org/rpc/?v=5&";
-const GITHUB_AUR_MIRROR_RAW_BASE: &str = "https://raw.githubusercontent.com/archlinux/aur";
+mod archive;
+mod chroot;
+mod config;
+mod deps;
+mod devel;
+mod history;
+mod index;
+mod pacmanconf;
+mod paths;
+mod pgp;
+mod pool;
+mod prompt;
+mod repo;
+mod review;
+mod srcinfo;
+mod vercmp;
+
+use config::{ Answer, Backend, Config, DataSource, OutputFormat, RemoveMakeDeps };
+use srcinfo::SrcInfo;
+use vercmp::vercmp;
 
 #[derive(Deserialize)]
 struct RpcResponse {
     results: Vec<AurPkg>,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 struct AurPkg {
     #[serde(rename = "Name")]
     name: String,
+    #[serde(rename = "PackageBase")]
+    package_base: Option<String>,
     #[serde(rename = "Version")]
     version: Option<String>,
     #[serde(rename = "Description")]
     description: Option<String>,
     #[serde(rename = "Popularity")]
-    popularity: Option<f32>,
+    popularity: Option<f64>,
+    #[serde(rename = "NumVotes")]
+    num_votes: Option<u64>,
     #[serde(rename = "Maintainer")]
     maintainer: Option<String>,
     #[serde(rename = "Depends")]
@@ -36,114 +61,197 @@ struct AurPkg {
     #[serde(rename = "MakeDepends")]
     #[serde(default)]
     make_depends: Vec<String>,
+    #[serde(rename = "CheckDepends")]
+    #[serde(default)]
+    check_depends: Vec<String>,
+    #[serde(rename = "Provides")]
+    #[serde(default)]
+    provides: Vec<String>,
+    #[serde(rename = "Conflicts")]
+    #[serde(default)]
+    conflicts: Vec<String>,
+    #[serde(rename = "Replaces")]
+    #[serde(default)]
+    replaces: Vec<String>,
+}
+
+impl AurPkg {
+    // Name of the AUR git repo / github mirror branch this package is built from
+    fn base(&self) -> &str {
+        self.package_base.as_deref().unwrap_or(&self.name)
+    }
+
+    // Metadata for sub-package `name` as far as .SRCINFO knows it (no votes, maintainer, ...)
+    fn from_srcinfo(srcinfo: &SrcInfo, name: &str) -> AurPkg {
+        AurPkg {
+            name: name.to_string(),
+            package_base: Some(srcinfo.pkgbase.clone()),
+            version: srcinfo.version(),
+            description: srcinfo.pkgdesc(name),
+            popularity: None,
+            num_votes: None,
+            maintainer: None,
+            depends: srcinfo.depends(name),
+            make_depends: srcinfo.make_depends(),
+            check_depends: srcinfo.check_depends(),
+            provides: srcinfo.provides(name),
+            conflicts: srcinfo.conflicts(name),
+            replaces: srcinfo.replaces(name),
+        }
+    }
 }
 
-// simple yes/no prompt
-fn prompt_yes(question: &str) -> bool {
-    print!("{} [Y/n] ", question);
-    io::stdout().flush().unwrap();
+// Exit codes other than 0 (success) and 1 (error); scripts may rely on them
+const EXIT_NOT_FOUND: i32 = 2;
+const EXIT_UPDATES_AVAILABLE: i32 = 3;
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    let resp = input.trim().to_lowercase();
-    resp.is_empty() || resp == "y" || resp == "yes"
+// Quote a command line argument for display if the shell would split or expand it
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty() &&
+        arg.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_./:=+,@%*".contains(&b));
+    if plain { arg.to_string() } else { format!("'{}'", arg.replace('\'', "'\\''")) }
+}
+
+// Run a command that changes the system. With --dry-run it is only printed (with the directory
+// it would run in) and treated as successful.
+fn run_cmd(cfg: &Config, cmd: &mut Shell) -> io::Result<bool> {
+    if cfg.dry_run {
+        let mut line = shell_quote(&cmd.get_program().to_string_lossy());
+        for arg in cmd.get_args() {
+            line.push(' ');
+            line.push_str(&shell_quote(&arg.to_string_lossy()));
+        }
+        match cmd.get_current_dir() {
+            Some(dir) => println!("  [{}] {}", dir.display(), line),
+            None => println!("  {}", line),
+        }
+        return Ok(true);
+    }
+    Ok(cmd.status()?.success())
 }
 
 // --- AUR RPC helpers ---
-fn fetch_search(term: &str) -> Result<Vec<AurPkg>, Box<dyn Error>> {
-    let url = format!("{}type=search&arg={}", AUR_RPC, term);
-    let resp: RpcResponse = get(&url)?.json()?;
-    let mut packages = resp.results;
+fn fetch_search(cfg: &Config, term: &str) -> Result<Vec<AurPkg>, Box<dyn Error>> {
+    let mut packages = if cfg.offline {
+        index::load()?.search(term)
+    } else {
+        let url = cfg.endpoints.aur_rpc_url(&format!("type=search&arg={}", encode_query_arg(term)));
+        let resp: RpcResponse = get(&url)?.json()?;
+        resp.results
+    };
     packages.sort_by(|a, b| {
         b.popularity.unwrap_or(0.0).partial_cmp(&a.popularity.unwrap_or(0.0)).unwrap()
     });
     Ok(packages)
 }
 
-fn fetch_info(name: &str) -> Result<AurPkg, Box<dyn Error>> {
-    let url = format!("{}type=info&arg={}", AUR_RPC, name);
-    let resp: RpcResponse = get(&url)?.json()?;
-    resp.results
-        .into_iter()
-        .next()
-        .ok_or_else(|| format!("Package '{}' not found", name).into())
+// AUR packages providing each of `names`, from the RPC's provides search (which also matches
+// package names) or the offline index. Searches run concurrently.
+fn fetch_providers(cfg: &Config, names: &[&String]) -> Result<Vec<Vec<AurPkg>>, Box<dyn Error>> {
+    if cfg.offline {
+        let index = index::load()?;
+        return Ok(
+            names
+                .iter()
+                .map(|n| index.providers(n))
+                .collect()
+        );
+    }
+    let urls: Vec<String> = names
+        .iter()
+        .map(|n| format!("type=search&by=provides&arg={}", encode_query_arg(n)))
+        .map(|query| cfg.endpoints.aur_rpc_url(&query))
+        .collect();
+    let responses = pool::map(&urls, cfg.jobs, |url| -> Result<RpcResponse, String> {
+        get(url)
+            .and_then(|r| r.json())
+            .map_err(|e| e.to_string())
+    });
+    let mut results = Vec::new();
+    for resp in responses {
+        results.push(resp?.results);
+    }
+    Ok(results)
+}
+
+// Keep info request URLs well below the AUR's request line limit
+const AUR_RPC_MAX_URL_LEN: usize = 4000;
+
+// Percent-encode a package name for use in a query string (names may contain '+', '@', ...)
+fn encode_query_arg(arg: &str) -> String {
+    let mut out = String::with_capacity(arg.len());
+    for b in arg.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
 }
 
-// --- GitHub PKGBUILD helpers ---
-// Fetch PKGBUILD from the GitHub aur mirror branch for package `pkg`
-// (raw URL: https://raw.githubusercontent.com/archlinux/aur/<branch>/PKGBUILD)
-fn fetch_pkgbuild_from_github(pkg: &str) -> Result<Option<String>, Box<dyn Error>> {
-    let url = format!("{}/{}/PKGBUILD", GITHUB_AUR_MIRROR_RAW_BASE, pkg);
+// Fetch info for many packages using the RPC's arg[]= form, split into as few requests as the
+// URL length limit allows (sent concurrently), or from the offline index. Packages that don't
+// exist in the AUR are simply absent from the result.
+fn fetch_info_many(cfg: &Config, names: &[String]) -> Result<Vec<AurPkg>, Box<dyn Error>> {
+    if cfg.offline {
+        let index = index::load()?;
+        return Ok(
+            names
+                .iter()
+                .filter_map(|n| index.get(n).cloned())
+                .collect()
+        );
+    }
+
+    let base = cfg.endpoints.aur_rpc_url("type=info");
+    let mut urls = Vec::new();
+    let mut url = base.clone();
+    let mut pending = 0;
+
+    for name in names {
+        let arg = format!("&arg[]={}", encode_query_arg(name));
+        if pending > 0 && url.len() + arg.len() > AUR_RPC_MAX_URL_LEN {
+            urls.push(url);
+            url = base.clone();
+            pending = 0;
+        }
+        url.push_str(&arg);
+        pending += 1;
+    }
+    if pending > 0 {
+        urls.push(url);
+    }
+
+    let responses = pool::map(&urls, cfg.jobs, |url| -> Result<RpcResponse, String> {
+        get(url)
+            .and_then(|r| r.json())
+            .map_err(|e| e.to_string())
+    });
+    let mut results = Vec::new();
+    for resp in responses {
+        results.extend(resp?.results);
+    }
+    Ok(results)
+}
+
+// --- GitHub .SRCINFO helpers ---
+// Fetch and parse .SRCINFO from the GitHub aur mirror branch for package `pkg`
+// (raw URL: https://raw.githubusercontent.com/archlinux/aur/<branch>/.SRCINFO)
+fn fetch_srcinfo_from_github(cfg: &Config, pkg: &str) -> Result<Option<SrcInfo>, Box<dyn Error>> {
+    let url = cfg.endpoints.mirror_raw_url(pkg, ".SRCINFO");
     let resp = get(&url)?;
     if !resp.status().is_success() {
         // Not found or HTTP error
         return Ok(None);
     }
     let body = resp.text()?;
-    Ok(Some(body))
-}
-
-// Parse pkgver and pkgrel from PKGBUILD text.
-// Returns combined version string like "1.2.3-4" (pkgver-pkgrel), or just "1.2.3" if pkgrel missing.
-fn parse_pkgbuild_version(build: &str) -> Option<String> {
-    // naive but practical parsing:
-    // look for lines like: pkgver=1.2.3 or pkgver='1.2.3' or pkgver="1.2.3"
-    // and pkgrel=4 or pkgrel='4'
-    let mut pkgver: Option<String> = None;
-    let mut pkgrel: Option<String> = None;
-
-    for line in build.lines() {
-        let l = line.trim();
-        // ignore comments
-        if l.starts_with('#') {
-            continue;
-        }
-        if l.starts_with("pkgver") && l.contains('=') {
-            if let Some(idx) = l.find('=') {
-                let mut val = l[idx + 1..].trim();
-                // strip quotes
-                if
-                    (val.starts_with('\'') && val.ends_with('\'')) ||
-                    (val.starts_with('"') && val.ends_with('"'))
-                {
-                    val = &val[1..val.len() - 1];
-                }
-                // ignore complex assignments (like pkgver=$(git describe ...))
-                if !val.contains('$') && !val.contains('(') {
-                    pkgver = Some(val.to_string());
-                } else {
-                    // complicated pkgver; bail out (cannot parse reliably)
-                    return None;
-                }
-            }
-        } else if l.starts_with("pkgrel") && l.contains('=') {
-            if let Some(idx) = l.find('=') {
-                let mut val = l[idx + 1..].trim();
-                if
-                    (val.starts_with('\'') && val.ends_with('\'')) ||
-                    (val.starts_with('"') && val.ends_with('"'))
-                {
-                    val = &val[1..val.len() - 1];
-                }
-                if !val.contains('$') && !val.contains('(') {
-                    pkgrel = Some(val.to_string());
-                } else {
-                    // complicated pkgrel; bail out
-                    return None;
-                }
-            }
-        }
-        // stop early if both found
-        if pkgver.is_some() && pkgrel.is_some() {
-            break;
-        }
-    }
-
-    match (pkgver, pkgrel) {
-        (Some(v), Some(r)) => Some(format!("{}-{}", v, r)),
-        (Some(v), None) => Some(v),
-        _ => None,
-    }
+    Ok(Some(SrcInfo::parse(&body)?))
 }
 
 // --- helper to get installed AUR packages and their installed versions ---
@@ -168,11 +276,11 @@ fn get_installed_aur() -> Result<Vec<(String, String)>, Box<dyn Error>> {
 }
 
 // --- other helpers ---
-fn fetch_github_packages() -> Result<Vec<String>, Box<dyn Error>> {
+fn fetch_github_packages(cfg: &Config) -> Result<Vec<String>, Box<dyn Error>> {
     let output = Shell::new("git")
         .arg("ls-remote")
         .arg("--heads")
-        .arg("https://github.com/archlinux/aur.git")
+        .arg(&cfg.endpoints.mirror_git)
         .output()?;
 
     if !output.status.success() {
@@ -203,195 +311,894 @@ fn is_debug_package(name: &str) -> bool {
 
 // --- Command implementations ---
 
-fn cmd_search(term: &str, use_github: bool) -> Result<(), Box<dyn Error>> {
-    if use_github {
-        println!("searching github mirror for '{}'", term);
-        let branches = fetch_github_packages()?;
+fn cmd_search(cfg: &Config, term: &str) -> Result<(), Box<dyn Error>> {
+    let json = cfg.format == OutputFormat::Json;
+    // the offline index replaces the git ls-remote of the mirror as well
+    if cfg.use_github() && !cfg.offline {
+        if !json {
+            println!("searching github mirror for '{}'", term);
+        }
+        let branches = fetch_github_packages(cfg)?;
         let mut matches: Vec<&String> = branches
             .iter()
             .filter(|b| b.contains(term))
             .collect();
         matches.sort();
-        println!("\nFound {} packages (github mirror):", matches.len());
-        for pkg in matches {
-            println!("\n{}", pkg);
+        if json {
+            let results: Vec<_> = matches
+                .iter()
+                .map(|name| serde_json::json!({ "Name": name }))
+                .collect();
+            print_json(&serde_json::json!({ "backend": DataSource::Github, "results": results }))?;
+        } else {
+            println!("\nFound {} packages (github mirror):", matches.len());
+            for pkg in &matches {
+                println!("\n{}", pkg);
+            }
+        }
+        if matches.is_empty() {
+            exit(EXIT_NOT_FOUND);
         }
         return Ok(());
     }
 
-    let packages = fetch_search(term)?;
-    println!("\nFound {} packages:", packages.len());
-    for pkg in packages {
-        println!("\n{} {}", pkg.name, pkg.version.as_deref().unwrap_or(""));
-        if let Some(desc) = &pkg.description {
-            println!("  {}", desc);
+    let packages = fetch_search(cfg, term)?;
+    if json {
+        print_json(&serde_json::json!({ "backend": cfg.data_source(), "results": packages }))?;
+    } else {
+        println!("\nFound {} packages:", packages.len());
+        for pkg in &packages {
+            println!("\n{} {}", pkg.name, pkg.version.as_deref().unwrap_or(""));
+            if let Some(desc) = &pkg.description {
+                println!("  {}", desc);
+            }
+            println!("  Popularity: {:.2}", pkg.popularity.unwrap_or(0.0));
         }
-        println!("  Popularity: {:.2}", pkg.popularity.unwrap_or(0.0));
+    }
+    if packages.is_empty() {
+        exit(EXIT_NOT_FOUND);
     }
     Ok(())
 }
 
-fn cmd_install(pkgs: &[String], use_github: bool) -> Result<(), Box<dyn Error>> {
-    let github_list = if use_github { Some(fetch_github_packages()?) } else { None };
+// Archives makepkg will produce in `dir` for the given sub-packages
+fn package_files(dir: &Path, pkgnames: &[&str]) -> Result<Vec<String>, Box<dyn Error>> {
+    let output = Shell::new("makepkg").arg("--packagelist").current_dir(dir).output()?;
+    if !output.status.success() {
+        return Err(format!("makepkg --packagelist failed in {}", dir.display()).into());
+    }
+    let files = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|path| archive::parse_name(path).is_some_and(|(name, _)| pkgnames.contains(&name)))
+        .map(|path| path.to_string())
+        .collect();
+    Ok(files)
+}
 
-    for pkg_name in pkgs {
-        if is_debug_package(pkg_name) {
-            // avoid cloning/building debug packages explicitly
-            println!("Skipping debug package install request: {}", pkg_name);
-            continue;
+// Clone package base `base` (from the AUR or the github mirror branch) into the build cache,
+// or bring an existing clone up to date with git pull. Returns Ok(false) if that failed.
+fn clone_package(cfg: &Config, base: &str) -> Result<bool, Box<dyn Error>> {
+    let use_github = cfg.use_github();
+    let dir = cfg.build_dir(base);
+    let (url, branch) = if use_github {
+        (cfg.endpoints.mirror_git.clone(), base)
+    } else {
+        (cfg.endpoints.aur_git_url(base), "master")
+    };
+
+    if dir.join(".git").is_dir() {
+        discard_changes(cfg, &dir)?;
+        let pulled = run_cmd(
+            cfg,
+            Shell::new("git").arg("-C").arg(&dir).args(["pull", "--ff-only", &url, branch])
+        )?;
+        if pulled {
+            return Ok(true);
         }
+        // history was rewritten or the clone is broken; start over
+        eprintln!("git pull failed for {}; cloning again", base);
+        fs::remove_dir_all(&dir)?;
+    }
 
-        if use_github {
-            if !github_package_exists(pkg_name, github_list.as_ref().unwrap()) {
-                eprintln!("package '{}' not found on github mirror, skipping", pkg_name);
-                continue;
-            }
+    if !cfg.dry_run {
+        fs::create_dir_all(cfg.build_root())?;
+    }
+    let mut git = Shell::new("git");
+    git.arg("clone");
+    if use_github {
+        git.args(["--single-branch", "--branch", branch]);
+    }
+    if !run_cmd(cfg, git.arg(&url).arg(&dir))? {
+        eprintln!("git clone failed for {} ({}).", base, if use_github { "mirror" } else { "aur" });
+        return Ok(false);
+    }
+    Ok(true)
+}
 
-            println!("\nInstalling from github mirror: {}", pkg_name);
-            if !prompt_yes("Proceed?") {
-                println!("Skipping {}", pkg_name);
-                continue;
+// Throw away changes to the tracked files of a clone. makepkg rewrites pkgver= in the PKGBUILD
+// of VCS packages, which would make every later pull or checkout fail. Untracked files (the
+// VCS sources and built archives) are kept.
+fn discard_changes(cfg: &Config, dir: &Path) -> io::Result<bool> {
+    run_cmd(cfg, Shell::new("git").arg("-C").arg(dir).args(["reset", "--hard", "--quiet"]))
+}
+
+// Build a cloned package base once with makepkg (or in the chroot), without installing anything.
+// Returns the archives of the sub-packages in `unit.pkgs`, or None if the build failed.
+fn build_package(
+    cfg: &Config,
+    unit: &deps::BuildUnit,
+    resolution: &deps::Resolution,
+    remove_deps: bool
+) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    let dir = cfg.build_dir(&unit.base);
+    let built = if cfg.chroot {
+        // the AUR dependencies were built before this unit; inject their archives, along with
+        // those of the AUR packages they need at run time, which pacman can't find otherwise
+        let mut inject = Vec::new();
+        for dep in resolution.dep_closure(unit) {
+            inject.extend(unit_archives(cfg, dep)?);
+        }
+        chroot::build(cfg, &dir, &inject)?
+    } else {
+        // -f: the cached build dir may still hold archives from an earlier build
+        let mut args = vec!["-s", "-f", "--noconfirm"];
+        if remove_deps {
+            args.push("--rmdeps");
+        }
+        run_cmd(cfg, Shell::new("makepkg").args(&args).current_dir(&dir))?
+    };
+    if !built {
+        return Ok(None);
+    }
+
+    let names = unit_names(unit);
+    let files = unit_archives(cfg, unit)?;
+    if files.len() != names.len() {
+        eprintln!("makepkg did not produce packages for all of: {}", names.join(" "));
+        return Ok(None);
+    }
+    if !cfg.dry_run && let Err(e) = archive::store(cfg, &files) {
+        eprintln!("Cannot keep the archives of {}: {}", unit.base, e);
+    }
+    Ok(Some(files))
+}
+
+// In a chroot build, AUR packages that are only needed to build others never reach the host;
+// they exist as archives injected into the chroot
+fn installs_on_host(cfg: &Config, unit: &deps::BuildUnit, resolution: &deps::Resolution) -> bool {
+    !cfg.chroot || resolution.needed_at_runtime(unit)
+}
+
+fn unit_names(unit: &deps::BuildUnit) -> Vec<&str> {
+    unit.pkgs
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect()
+}
+
+// Archives of the sub-packages in `unit.pkgs`. A dry run has not cloned or built anything, so
+// it names the archives after the AUR versions instead of asking makepkg.
+fn unit_archives(cfg: &Config, unit: &deps::BuildUnit) -> Result<Vec<String>, Box<dyn Error>> {
+    let dir = cfg.build_dir(&unit.base);
+    if !cfg.dry_run {
+        return package_files(&dir, &unit_names(unit));
+    }
+    Ok(
+        unit.pkgs
+            .iter()
+            .map(|p| {
+                let file = format!("{}-{}-*.pkg.tar.zst", p.name, p.version.as_deref().unwrap_or("*"));
+                dir.join(file).display().to_string()
+            })
+            .collect()
+    )
+}
+
+// Installed versions of those of `names` that are installed
+fn installed_versions(names: &[&str]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    // pacman -Q fails for names that aren't installed but still prints the others
+    let output = Shell::new("pacman").arg("-Q").args(names).output()?;
+    Ok(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(name, ver)| (name.to_string(), ver.to_string()))
+            .collect()
+    )
+}
+
+// Revision of the clone a package base was built from
+fn build_commit(cfg: &Config, base: &str) -> Option<String> {
+    let output = Shell::new("git")
+        .arg("-C")
+        .arg(cfg.build_dir(base))
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// History entries for the sub-packages of `unit`; `before` are the versions installed before
+fn history_entries(
+    cfg: &Config,
+    unit: &deps::BuildUnit,
+    before: &HashMap<String, String>,
+    outcome: history::Outcome
+) -> Vec<history::Entry> {
+    let commit = build_commit(cfg, &unit.base);
+    unit.pkgs
+        .iter()
+        .map(|pkg| {
+            let old_version = before.get(&pkg.name).cloned();
+            let action = match old_version {
+                Some(_) => history::Action::Update,
+                None => history::Action::Install,
+            };
+            let mut entry = history::Entry::new(action, &pkg.name, outcome);
+            entry.old_version = old_version;
+            entry.new_version = pkg.version.clone();
+            entry.backend = Some(cfg.backend);
+            entry.commit = commit.clone();
+            entry
+        })
+        .collect()
+}
+
+fn record_build_failure(cfg: &Config, unit: &deps::BuildUnit) {
+    if cfg.dry_run {
+        return;
+    }
+    let before = installed_versions(&unit_names(unit)).unwrap_or_default();
+    history::append(cfg, &history_entries(cfg, unit, &before, history::Outcome::BuildFailed));
+}
+
+// Install archives of the packages `names` with pacman -U, or through the local repository so
+// that its database lists the installed versions
+fn install_files(cfg: &Config, files: &[&String], names: &[&str]) -> Result<bool, Box<dyn Error>> {
+    match &cfg.local_repo {
+        Some(local) => Ok(repo::add(cfg, local, files)? && repo::install(cfg, local, names)?),
+        None => Ok(run_cmd(cfg, Shell::new("sudo").args(["pacman", "-U", "--noconfirm"]).args(files))?),
+    }
+}
+
+// Install the archives built for `built` in one transaction and record the result in the
+// history. Newly installed packages that are not explicitly requested are marked as
+// dependencies. Returns Ok(false) if pacman failed.
+fn install_packages(
+    cfg: &Config,
+    built: &[(&deps::BuildUnit, Vec<String>)],
+    resolution: &deps::Resolution
+) -> Result<bool, Box<dyn Error>> {
+    let names: Vec<&str> = built
+        .iter()
+        .flat_map(|(unit, _)| unit_names(unit))
+        .collect();
+    let files: Vec<&String> = built
+        .iter()
+        .flat_map(|(_, files)| files)
+        .collect();
+    // packages that are already installed keep their install reason
+    let before = installed_versions(&names)?;
+    let deps: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|n| !resolution.is_explicit(n) && !before.contains_key(*n))
+        .collect();
+
+    let installed = install_files(cfg, &files, &names)?;
+    if installed && !deps.is_empty() {
+        run_cmd(cfg, Shell::new("sudo").args(["pacman", "-D", "--asdeps"]).args(&deps))?;
+    }
+
+    if !cfg.dry_run {
+        // the built version may differ from the AUR's (VCS packages), so ask pacman
+        let after = if installed { installed_versions(&names)? } else { HashMap::new() };
+        let outcome = match installed {
+            true => history::Outcome::Success,
+            false => history::Outcome::InstallFailed,
+        };
+        let mut entries = Vec::new();
+        for (unit, _) in built {
+            for mut entry in history_entries(cfg, unit, &before, outcome) {
+                if let Some(ver) = after.get(&entry.package) {
+                    entry.new_version = Some(ver.clone());
+                }
+                if let (Some(old), Some(new)) = (&entry.old_version, &entry.new_version) &&
+                    vercmp(new, old) == Ordering::Less
+                {
+                    entry.action = history::Action::Downgrade;
+                }
+                entries.push(entry);
             }
+        }
+        history::append(cfg, &entries);
+    }
+    Ok(installed)
+}
 
-            let status = Shell::new("git")
-                .arg("clone")
-                .arg("--single-branch")
-                .arg("--branch")
-                .arg(pkg_name)
-                .arg("https://github.com/archlinux/aur.git")
-                .arg(pkg_name)
-                .status()?;
-
-            if !status.success() {
-                eprintln!("git clone failed for {} (mirror).", pkg_name);
+// Build a cloned package base and install only the sub-packages in `unit.pkgs`.
+// Returns Ok(false) if building or installing failed.
+fn make_and_install(
+    cfg: &Config,
+    unit: &deps::BuildUnit,
+    resolution: &deps::Resolution,
+    remove_deps: bool
+) -> Result<bool, Box<dyn Error>> {
+    let files = match build_package(cfg, unit, resolution, remove_deps)? {
+        Some(files) => files,
+        None => {
+            record_build_failure(cfg, unit);
+            return Ok(false);
+        }
+    };
+    if !install_packages(cfg, &[(unit, files)], resolution)? {
+        return Ok(false);
+    }
+    record_devel(cfg, unit);
+    Ok(true)
+}
+
+// Remember which revisions of the VCS sources were built, for `update --devel`
+fn record_devel(cfg: &Config, unit: &deps::BuildUnit) {
+    if cfg.dry_run {
+        return;
+    }
+    let dir = cfg.build_dir(&unit.base);
+    let recorded = read_srcinfo(&dir)
+        .and_then(|srcinfo| devel::record(&unit.base, &dir, &unit_names(unit), &srcinfo));
+    if let Err(e) = recorded {
+        eprintln!("Cannot record VCS sources of {}: {}", unit.base, e);
+    }
+}
+
+// Build every unit first and install all of them with one pacman -U only if every build
+// succeeded. Bases that later builds depend on have to be installed before those builds run,
+// except in the chroot, where they are injected instead.
+fn build_then_install(
+    cfg: &Config,
+    units: &[&deps::BuildUnit],
+    resolution: &deps::Resolution,
+    remove_deps: bool
+) -> Result<(), Box<dyn Error>> {
+    let mut installed: Vec<&str> = Vec::new();
+    let mut pending: Vec<(&deps::BuildUnit, Vec<String>)> = Vec::new();
+    for unit in units {
+        let early: &[String] = if cfg.chroot { &[] } else { &unit.deps };
+        for dep in early {
+            let Some(idx) = pending.iter().position(|(u, _)| &u.base == dep) else {
                 continue;
+            };
+            let (dep_unit, files) = pending.remove(idx);
+            println!("\nInstalling {} now, it is needed to build {}", dep_unit.base, unit.base);
+            if !install_packages(cfg, &[(dep_unit, files)], resolution)? {
+                return Err(format!("failed to install {}; nothing else was installed", dep).into());
             }
+            record_devel(cfg, dep_unit);
+            installed.push(&dep_unit.base);
+        }
 
-            let remove_deps = prompt_yes("Remove make dependencies after build?");
-            let mut args = vec!["-si", "--noconfirm"];
-            if remove_deps {
-                args.push("--rmdeps");
+        println!("\nBuilding {}", unit.base);
+        match build_package(cfg, unit, resolution, remove_deps)? {
+            Some(_) if !installs_on_host(cfg, unit, resolution) => {}
+            Some(files) => pending.push((unit, files)),
+            None => {
+                record_build_failure(cfg, unit);
+                let note = if installed.is_empty() {
+                    "nothing was installed".to_string()
+                } else {
+                    format!("only the build dependencies {} were installed", installed.join(", "))
+                };
+                return Err(format!("failed to build {}; {}", unit.base, note).into());
             }
+        }
+    }
 
-            let status = Shell::new("makepkg").args(&args).current_dir(pkg_name).status()?;
-            let _ = fs::remove_dir_all(pkg_name);
+    let names: Vec<&str> = pending
+        .iter()
+        .flat_map(|(unit, _)| unit_names(unit))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+    println!("\nInstalling {}", names.join(" "));
+    if !install_packages(cfg, &pending, resolution)? {
+        return Err("pacman -U failed; the built packages were not installed".into());
+    }
+    for (unit, _) in &pending {
+        record_devel(cfg, unit);
+    }
+    if !cfg.dry_run {
+        println!("Successfully installed {}", names.join(" "));
+    }
+    Ok(())
+}
 
-            if status.success() {
-                println!("Successfully installed {}", pkg_name);
-            } else {
-                eprintln!("Failed to install {} (build error).", pkg_name);
-            }
+// .SRCINFO of a clone, generated with makepkg if the clone doesn't have one
+fn read_srcinfo(dir: &Path) -> Result<SrcInfo, Box<dyn Error>> {
+    let text = match fs::read_to_string(dir.join(".SRCINFO")) {
+        Ok(text) => text,
+        Err(_) => {
+            let output = Shell::new("makepkg").arg("--printsrcinfo").current_dir(dir).output()?;
+            String::from_utf8(output.stdout)?
+        }
+    };
+    SrcInfo::parse(&text)
+}
+
+// Print the ordered build plan so it can be confirmed before anything is cloned
+fn print_build_plan(cfg: &Config, resolution: &deps::Resolution) {
+    if !resolution.repo_deps.is_empty() {
+        println!("\nRepo dependencies (installed by makepkg):");
+        for dep in &resolution.repo_deps {
+            println!("  {}", dep);
+        }
+    }
+    println!("\nBuild plan{}:", if cfg.use_github() { " (github mirror)" } else { "" });
+    for (i, unit) in resolution.build.iter().enumerate() {
+        let pkgs: Vec<String> = unit.pkgs
+            .iter()
+            .map(|pkg| {
+                format!(
+                    "{} {}{}",
+                    pkg.name,
+                    pkg.version.as_deref().unwrap_or(""),
+                    if resolution.is_explicit(&pkg.name) {
+                        ""
+                    } else if !installs_on_host(cfg, unit, resolution) {
+                        " (build dependency, chroot only)"
+                    } else {
+                        " (dependency)"
+                    }
+                )
+            })
+            .collect();
+        if unit.pkgs.len() == 1 && unit.pkgs[0].name == unit.base {
+            println!("  {}. {}", i + 1, pkgs[0]);
         } else {
-            let pkg = match fetch_info(pkg_name) {
-                Ok(p) => p,
-                Err(e) => {
-                    eprintln!("failed to fetch info for {}: {}", pkg_name, e);
-                    continue;
-                }
-            };
+            println!("  {}. [{}] {}", i + 1, unit.base, pkgs.join(", "));
+        }
+    }
+    if !resolution.conflicts.is_empty() {
+        println!("\nConflicts (pacman will refuse to install until they are resolved):");
+        for conflict in &resolution.conflicts {
+            println!("  {}", conflict);
+        }
+    }
+    if cfg.transaction {
+        let early: Vec<&str> = resolution.build
+            .iter()
+            .filter(|u| {
+                !cfg.chroot && resolution.build.iter().any(|other| other.deps.contains(&u.base))
+            })
+            .map(|u| u.base.as_str())
+            .collect();
+        println!("\nEverything is installed at once after all builds succeed.");
+        if !early.is_empty() {
+            println!("Installed right after building (needed by later builds): {}", early.join(", "));
+        }
+    }
+}
 
-            println!("\nInstalling: {} {}", pkg.name, pkg.version.as_deref().unwrap_or(""));
-            if !prompt_yes("Proceed?") {
-                println!("Skipping {}", pkg.name);
-                continue;
-            }
+fn cmd_install(cfg: &Config, pkgs: &[String]) -> Result<(), Box<dyn Error>> {
+    let use_github = cfg.use_github();
+    let github_list = if use_github { Some(fetch_github_packages(cfg)?) } else { None };
+
+    let mut targets: Vec<String> = Vec::new();
+    for pkg_name in pkgs {
+        if is_debug_package(pkg_name) {
+            // avoid cloning/building debug packages explicitly
+            println!("Skipping debug package install request: {}", pkg_name);
+            continue;
+        }
+        targets.push(pkg_name.clone());
+    }
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(local) = &cfg.local_repo {
+        repo::check(cfg, local)?;
+    }
+
+    println!("Resolving dependencies...");
+    let mut resolution = deps::resolve(cfg, &targets)?;
 
-            let repo_url = format!("https://aur.archlinux.org/{}.git", pkg.name);
-            let status = Shell::new("git").arg("clone").arg(&repo_url).status()?;
-            if !status.success() {
-                eprintln!("git clone failed for {} (aur).", pkg.name);
+    let mut unresolved: Vec<&String> = Vec::new();
+    for name in &resolution.missing {
+        if resolution.is_explicit(name) {
+            eprintln!("failed to fetch info for {}: Package '{}' not found", name, name);
+        } else {
+            unresolved.push(name);
+        }
+    }
+    if !unresolved.is_empty() {
+        let list: Vec<&str> = unresolved.iter().map(|s| s.as_str()).collect();
+        return Err(
+            format!("could not find dependencies in the repos or the AUR: {}", list.join(", ")).into()
+        );
+    }
+
+    // github mirror branches are named after the package base
+    if let Some(list) = &github_list {
+        let mut missing_bases = Vec::new();
+        for unit in &resolution.build {
+            if github_package_exists(&unit.base, list) {
                 continue;
             }
+            if unit.pkgs.iter().any(|p| !resolution.is_explicit(&p.name)) {
+                return Err(
+                    format!("dependency '{}' not found on github mirror", unit.base).into()
+                );
+            }
+            eprintln!("package '{}' not found on github mirror, skipping", unit.base);
+            missing_bases.push(unit.base.clone());
+        }
+        resolution.build.retain(|u| !missing_bases.contains(&u.base));
+    }
+    if resolution.build.is_empty() {
+        return Ok(());
+    }
+
+    print_build_plan(cfg, &resolution);
 
-            let remove_deps = prompt_yes("Remove make dependencies after build?");
-            let mut args = vec!["-si", "--noconfirm"];
-            if remove_deps {
-                args.push("--rmdeps");
+    if cfg.dry_run {
+        println!("\nDry run, nothing will be changed. These commands would run:");
+    } else if !prompt::confirm(cfg, "Proceed?", cfg.prompts.proceed)? {
+        println!("Aborting");
+        return Ok(());
+    }
+
+    // clone everything first so all build files are reviewed before anything is built
+    let mut cloned: Vec<&deps::BuildUnit> = Vec::new();
+    for unit in &resolution.build {
+        if clone_package(cfg, &unit.base)? {
+            cloned.push(unit);
+        } else if unit.pkgs.iter().any(|p| !resolution.is_explicit(&p.name)) {
+            return Err(format!("failed to clone dependency {}", unit.base).into());
+        }
+    }
+
+    // a dry run has nothing cloned to review
+    if !cfg.dry_run {
+        for unit in &cloned {
+            if !review::review(cfg, &unit.base, &cfg.build_dir(&unit.base))? {
+                println!("Aborting");
+                return Ok(());
             }
+        }
+    }
+
+    let bases: Vec<&str> = cloned.iter().map(|u| u.base.as_str()).collect();
+    pgp::check(cfg, &bases)?;
 
-            let status = Shell::new("makepkg").args(&args).current_dir(&pkg.name).status()?;
-            let _ = fs::remove_dir_all(&pkg.name);
+    if cfg.chroot {
+        chroot::prepare(cfg)?;
+    }
+    // a chroot copy is thrown away after every build, so there is nothing to remove
+    let remove_deps = match cfg.remove_make_deps {
+        _ if cfg.chroot => false,
+        RemoveMakeDeps::Ask if cfg.dry_run => cfg.prompts.remove_make_deps,
+        RemoveMakeDeps::Ask => {
+            let question = "Remove make dependencies after build?";
+            prompt::confirm(cfg, question, cfg.prompts.remove_make_deps)?
+        }
+        RemoveMakeDeps::Always => true,
+        RemoveMakeDeps::Never => false,
+    };
 
-            if status.success() {
-                println!("Successfully installed {}", pkg.name);
-            } else {
-                eprintln!("Failed to install {} (build error).", pkg.name);
+    if cfg.transaction {
+        return build_then_install(cfg, &cloned, &resolution, remove_deps);
+    }
+    for unit in &cloned {
+        let names = unit_names(unit);
+        println!("\nBuilding {}", unit.base);
+        if !installs_on_host(cfg, unit, &resolution) {
+            if build_package(cfg, unit, &resolution, remove_deps)?.is_none() {
+                record_build_failure(cfg, unit);
+                return Err(
+                    format!("failed to build dependency {}; aborting remaining builds", unit.base).into()
+                );
             }
+            continue;
+        }
+        if make_and_install(cfg, unit, &resolution, remove_deps)? {
+            if !cfg.dry_run {
+                println!("Successfully installed {}", names.join(" "));
+            }
+        } else if unit.pkgs.iter().all(|p| resolution.is_explicit(&p.name)) {
+            eprintln!("Failed to install {} (build error).", names.join(" "));
+        } else {
+            return Err(
+                format!("failed to build dependency {}; aborting remaining builds", unit.base).into()
+            );
         }
     }
     Ok(())
 }
 
-// --- Update logic: compare installed version to PKGBUILD version (GitHub) or AUR RPC (normal)
-fn cmd_update(use_github: bool, bypass: &bool) -> Result<(), Box<dyn Error>> {
-    check_root(bypass);
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum UpdateStatus {
+    Current,
+    Outdated,
+    LocalNewer,
+    // VCS package whose upstream sources have new commits (update --devel)
+    Devel,
+    // outdated, but kept back by the hold list or pacman.conf's IgnorePkg/IgnoreGroup
+    Held,
+    Ignored,
+    NotFound,
+}
 
-    println!("Checking for updates...");
+// Result of checking one installed AUR package against its remote version
+#[derive(Serialize)]
+struct UpdateRecord {
+    name: String,
+    installed_version: String,
+    remote_version: Option<String>,
+    // where remote_version came from: the github mirror, the AUR RPC or the offline index
+    backend: Option<DataSource>,
+    outdated: bool,
+    status: UpdateStatus,
+    // why a Held package is kept back: "hold", "IgnorePkg" or "IgnoreGroup <group>"
+    held_by: Option<String>,
+    // AUR metadata of the package, absent if it was ignored or isn't in the AUR
+    package: Option<AurPkg>,
+}
 
-    let installed = get_installed_aur()?;
-    if installed.is_empty() {
-        println!("No AUR packages installed");
-        return Ok(());
-    }
+// Compare installed AUR packages against the .SRCINFO version (GitHub) or the AUR RPC (normal)
+fn check_updates(cfg: &Config) -> Result<Vec<UpdateRecord>, Box<dyn Error>> {
+    let use_github = cfg.use_github();
+    let text = cfg.format == OutputFormat::Text;
 
-    let mut to_update: Vec<String> = Vec::new();
+    let installed: Vec<(String, String)> = get_installed_aur()?
+        .into_iter()
+        .filter(|(name, _)| {
+            if is_debug_package(name) {
+                if text {
+                    println!("Skipping debug package: {}", name);
+                }
+                return false;
+            }
+            true
+        })
+        .collect();
 
+    let mut records = Vec::new();
+    let mut checked: Vec<(String, String)> = Vec::new();
     for (name, installed_ver) in installed {
-        if is_debug_package(&name) {
-            println!("Skipping debug package: {}", name);
-            continue;
+        if cfg.ignore.contains(&name) {
+            records.push(UpdateRecord {
+                name,
+                installed_version: installed_ver,
+                remote_version: None,
+                backend: None,
+                outdated: false,
+                status: UpdateStatus::Ignored,
+                held_by: None,
+                package: None,
+            });
+        } else {
+            checked.push((name, installed_ver));
+        }
+    }
+    if checked.is_empty() {
+        return Ok(records);
+    }
+
+    // one batched RPC round gives the AUR versions and the package base of every installed package
+    let names: Vec<String> = checked
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut rpc_info: HashMap<String, AurPkg> = match fetch_info_many(cfg, &names) {
+        Ok(pkgs) => pkgs
+            .into_iter()
+            .map(|p| (p.name.clone(), p))
+            .collect(),
+        Err(e) if use_github => {
+            eprintln!("Cannot fetch AUR RPC info: {}; using github mirror only", e);
+            HashMap::new()
         }
+        Err(e) => {
+            return Err(e);
+        }
+    };
+
+    let mut remote_versions: HashMap<String, (String, DataSource)> = HashMap::new();
+    if use_github {
+        // mirror branches are named after the package base; fetch each base only once
+        let mut bases: Vec<String> = names
+            .iter()
+            .map(|name| rpc_info.get(name).map_or(name.as_str(), |p| p.base()).to_string())
+            .collect();
+        bases.sort();
+        bases.dedup();
 
-        if use_github {
-            // try to fetch PKGBUILD quickly via raw GitHub URL and parse pkgver/pkgrel
-            match fetch_pkgbuild_from_github(&name) {
-                Ok(Some(pkgb)) => {
-                    if let Some(remote_ver) = parse_pkgbuild_version(&pkgb) {
-                        if remote_ver != installed_ver {
-                            to_update.push(name.clone());
+        let fetched = pool::map(&bases, cfg.jobs, |base| {
+            fetch_srcinfo_from_github(cfg, base).map_err(|e| e.to_string())
+        });
+        let mut base_versions: HashMap<&str, String> = HashMap::new();
+        for (base, result) in bases.iter().zip(fetched) {
+            // fetch .SRCINFO via raw GitHub URL and read the version from it
+            match result {
+                Ok(Some(srcinfo)) => {
+                    match srcinfo.version() {
+                        Some(ver) => {
+                            base_versions.insert(base, ver);
+                        }
+                        None => {
+                            eprintln!(".SRCINFO for {} has no pkgver; falling back to AUR RPC", base);
                         }
-                        continue;
-                    } else {
-                        // Could not parse PKGBUILD (dynamic pkgver). Fall back to AUR RPC if possible.
-                        eprintln!("Could not parse PKGBUILD version for {}; falling back to AUR RPC", name);
                     }
                 }
                 Ok(None) => {
-                    eprintln!("No PKGBUILD found for {} on GitHub mirror; falling back to AUR RPC", name);
+                    eprintln!("No .SRCINFO found for {} on GitHub mirror; falling back to AUR RPC", base);
                 }
                 Err(e) => {
                     eprintln!(
-                        "Error fetching PKGBUILD for {}: {}; falling back to AUR RPC",
-                        name,
+                        "Error fetching .SRCINFO for {}: {}; falling back to AUR RPC",
+                        base,
                         e
                     );
                 }
             }
-            // fallback to RPC if github PKGBUILD missing or unparseable
-            match fetch_info(&name) {
-                Ok(pkg) => {
-                    let rpc_ver = pkg.version.unwrap_or_default();
-                    if rpc_ver != installed_ver {
-                        to_update.push(name.clone());
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Cannot fetch AUR RPC info for {}: {}; skipping", name, e);
+        }
+        for name in &names {
+            let base = rpc_info.get(name).map_or(name.as_str(), |p| p.base());
+            if let Some(ver) = base_versions.get(base) {
+                remote_versions.insert(name.clone(), (ver.clone(), DataSource::Github));
+            }
+        }
+    }
+
+    // normal AUR RPC path, also the fallback if github .SRCINFO is missing or unparseable
+    let rpc_source = if cfg.offline { DataSource::Offline } else { DataSource::Aur };
+    for (name, pkg) in &rpc_info {
+        if !remote_versions.contains_key(name) {
+            let ver = pkg.version.clone().unwrap_or_default();
+            remote_versions.insert(name.clone(), (ver, rpc_source));
+        }
+    }
+
+    for (name, installed_ver) in checked {
+        let record = match remote_versions.remove(&name) {
+            Some((remote_ver, backend)) => {
+                let status = match vercmp(&remote_ver, &installed_ver) {
+                    Ordering::Greater => UpdateStatus::Outdated,
+                    Ordering::Less => UpdateStatus::LocalNewer,
+                    Ordering::Equal => UpdateStatus::Current,
+                };
+                UpdateRecord {
+                    package: rpc_info.remove(&name),
+                    name,
+                    installed_version: installed_ver,
+                    remote_version: Some(remote_ver),
+                    backend: Some(backend),
+                    outdated: status == UpdateStatus::Outdated,
+                    status,
+                    held_by: None,
                 }
             }
+            None =>
+                UpdateRecord {
+                    name,
+                    installed_version: installed_ver,
+                    remote_version: None,
+                    backend: None,
+                    outdated: false,
+                    status: UpdateStatus::NotFound,
+                    held_by: None,
+                    package: None,
+                },
+        };
+        records.push(record);
+    }
+
+    // VCS packages usually report a local version newer than the AUR; ask upstream instead
+    if cfg.devel {
+        let candidates: Vec<String> = records
+            .iter()
+            .filter(|r| matches!(r.status, UpdateStatus::Current | UpdateStatus::LocalNewer))
+            .map(|r| r.name.clone())
+            .collect();
+        let check = devel::check(cfg, &candidates)?;
+        for name in &check.untracked {
+            eprintln!("No recorded VCS sources for {}; reinstall it to track upstream commits", name);
+        }
+        for record in records.iter_mut().filter(|r| check.outdated.contains(&r.name)) {
+            record.status = UpdateStatus::Devel;
+            record.outdated = true;
+        }
+    }
+
+    // held packages are still checked so the summary can say what they are missing
+    let pacman_conf = pacmanconf::PacmanConf::load(&cfg.pacman_conf)?;
+    let groups = pacman_conf.ignored_group_members();
+    for record in records.iter_mut().filter(|r| r.outdated) {
+        let held_by = if cfg.hold.contains(&record.name) {
+            Some("hold".to_string())
         } else {
-            // normal AUR RPC path
-            match fetch_info(&name) {
-                Ok(pkg) => {
-                    let rpc_ver = pkg.version.unwrap_or_default();
-                    if rpc_ver != installed_ver {
-                        to_update.push(name.clone());
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Cannot fetch AUR RPC info for {}: {}; skipping", name, e);
-                }
+            pacman_conf.held_by(&record.name, &groups)
+        };
+        if held_by.is_some() {
+            record.status = UpdateStatus::Held;
+            record.outdated = false;
+            record.held_by = held_by;
+        }
+    }
+    records.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(records)
+}
+
+// Check for updates and install them, or with `check_only` (always in JSON mode) just report them.
+// Exits with EXIT_UPDATES_AVAILABLE if a check finds outdated packages.
+fn cmd_update(cfg: &Config, bypass: &bool, check_only: bool) -> Result<(), Box<dyn Error>> {
+    check_root(bypass);
+
+    if cfg.format == OutputFormat::Json {
+        let records = check_updates(cfg)?;
+        print_json(&records)?;
+        if records.iter().any(|r| r.outdated) {
+            exit(EXIT_UPDATES_AVAILABLE);
+        }
+        return Ok(());
+    }
+
+    println!("Checking for updates...");
+    let records = check_updates(cfg)?;
+    if records.is_empty() {
+        println!("No AUR packages installed");
+        return Ok(());
+    }
+
+    let mut to_update: Vec<String> = Vec::new();
+    let mut local_newer: Vec<&UpdateRecord> = Vec::new();
+    let mut held: Vec<&UpdateRecord> = Vec::new();
+    for record in &records {
+        match record.status {
+            UpdateStatus::Ignored => println!("Ignoring {}", record.name),
+            UpdateStatus::Held => held.push(record),
+            UpdateStatus::NotFound => {
+                let source = match cfg.data_source() {
+                    DataSource::Offline => "the offline index",
+                    DataSource::Github => "the github mirror or the AUR RPC",
+                    DataSource::Aur => "the AUR RPC",
+                };
+                eprintln!("Package '{}' not found in {}; skipping", record.name, source);
             }
+            UpdateStatus::LocalNewer => local_newer.push(record),
+            UpdateStatus::Outdated | UpdateStatus::Devel => to_update.push(record.name.clone()),
+            UpdateStatus::Current => {}
+        }
+    }
+
+    if !local_newer.is_empty() {
+        println!("\nLocal version is newer than {}:", if cfg.use_github() {
+            "the github mirror"
+        } else {
+            "the AUR"
+        });
+        for record in &local_newer {
+            println!(
+                "  {} {} (remote: {})",
+                record.name,
+                record.installed_version,
+                record.remote_version.as_deref().unwrap_or("")
+            );
+        }
+    }
+
+    if !held.is_empty() {
+        println!("\nHeld (not updated):");
+        for record in &held {
+            println!(
+                "  {} {} -> {} ({})",
+                record.name,
+                record.installed_version,
+                record.remote_version.as_deref().unwrap_or(""),
+                record.held_by.as_deref().unwrap_or("")
+            );
         }
     }
 
@@ -400,90 +1207,431 @@ fn cmd_update(use_github: bool, bypass: &bool) -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
+    if check_only {
+        println!("\n{} update(s) available:", to_update.len());
+        for record in records.iter().filter(|r| r.outdated) {
+            if record.status == UpdateStatus::Devel {
+                println!("  {} {} (new upstream commits)", record.name, record.installed_version);
+                continue;
+            }
+            println!(
+                "  {} {} -> {}",
+                record.name,
+                record.installed_version,
+                record.remote_version.as_deref().unwrap_or("")
+            );
+        }
+        exit(EXIT_UPDATES_AVAILABLE);
+    }
+
     println!("Updating {} package(s)...", to_update.len());
-    cmd_install(&to_update, use_github)?;
+    cmd_install(cfg, &to_update)?;
     Ok(())
 }
 
-fn cmd_info(pkg_name: &str, use_github: bool) -> Result<(), Box<dyn Error>> {
-    if use_github {
-        match fetch_pkgbuild_from_github(pkg_name)? {
-            Some(pkgb) => {
-                if let Some(ver) = parse_pkgbuild_version(&pkgb) {
-                    println!("\nPackage: {} (from github mirror)", pkg_name);
-                    println!("Version (from PKGBUILD): {}", ver);
-                    println!("Source: https://github.com/archlinux/aur (branch = pkg name)");
-                    println!(
-                        "Note: PKGBUILD parsing is naive; some PKGBUILDs compute version dynamically."
-                    );
-                    return Ok(());
-                } else {
-                    println!("PKGBUILD found but version could not be parsed (dynamic/complex).");
-                }
-            }
+fn print_list(title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    println!("\n{}:", title);
+    for item in items {
+        println!("  - {}", item);
+    }
+}
+
+// Show package details. Exits with EXIT_NOT_FOUND if the package doesn't exist.
+fn cmd_info(cfg: &Config, pkg_name: &str) -> Result<(), Box<dyn Error>> {
+    let json = cfg.format == OutputFormat::Json;
+    let rpc_pkg = match fetch_info_many(cfg, &[pkg_name.to_string()]) {
+        Ok(pkgs) => pkgs.into_iter().next(),
+        // the mirror doesn't need the RPC, so only fail if we're not using it
+        Err(_) if cfg.use_github() && !cfg.offline => None,
+        Err(e) => {
+            return Err(e);
+        }
+    };
+
+    if cfg.use_github() && !cfg.offline {
+        // the mirror branch is named after the package base, which only the RPC knows
+        let branch = rpc_pkg.as_ref().map_or(pkg_name.to_string(), |p| p.base().to_string());
+        let srcinfo = match fetch_srcinfo_from_github(cfg, &branch)? {
+            Some(s) => s,
             None => {
-                return Err(format!("package '{}' not found on github mirror", pkg_name).into());
+                eprintln!("package '{}' not found on github mirror", pkg_name);
+                exit(EXIT_NOT_FOUND);
             }
+        };
+        if json {
+            let pkg = AurPkg::from_srcinfo(&srcinfo, pkg_name);
+            return print_json(&serde_json::json!({ "backend": DataSource::Github, "package": pkg }));
         }
+        println!("\nPackage: {} (from github mirror)", pkg_name);
+        if srcinfo.pkgbase != pkg_name {
+            println!("Package Base: {}", srcinfo.pkgbase);
+        }
+        println!("Version: {}", srcinfo.version().as_deref().unwrap_or("Unknown"));
+        println!("Source: {} (branch = {})", cfg.endpoints.mirror_git, branch);
+        if let Some(desc) = srcinfo.pkgdesc(pkg_name) {
+            println!("\nDescription:\n  {}", desc);
+        }
+        print_list("Dependencies", &srcinfo.depends(pkg_name));
+        print_list("Build Dependencies", &srcinfo.make_depends());
+        print_list("Check Dependencies", &srcinfo.check_depends());
+        print_list("Provides", &srcinfo.provides(pkg_name));
+        print_list("Conflicts", &srcinfo.conflicts(pkg_name));
+        print_list("Replaces", &srcinfo.replaces(pkg_name));
+        return Ok(());
+    }
+
+    let pkg = match rpc_pkg {
+        Some(p) => p,
+        None => {
+            eprintln!("Package '{}' not found", pkg_name);
+            exit(EXIT_NOT_FOUND);
+        }
+    };
+    if json {
+        return print_json(&serde_json::json!({ "backend": cfg.data_source(), "package": pkg }));
     }
-    let pkg = fetch_info(pkg_name)?;
     println!("\nPackage: {}", pkg.name);
+    if pkg.base() != pkg.name {
+        println!("Package Base: {}", pkg.base());
+    }
     println!("Version: {}", pkg.version.as_deref().unwrap_or("Unknown"));
     println!("Maintainer: {}", pkg.maintainer.as_deref().unwrap_or("None"));
     println!("Popularity: {:.2}", pkg.popularity.unwrap_or(0.0));
-    if !pkg.description.as_ref().map_or(true, |s| s.is_empty()) {
+    println!("Votes: {}", pkg.num_votes.unwrap_or(0));
+    if !pkg.description.as_ref().is_none_or(|s| s.is_empty()) {
         println!("\nDescription:\n  {}", pkg.description.unwrap());
     }
-    if !pkg.depends.is_empty() {
-        println!("\nDependencies:");
-        for dep in &pkg.depends {
-            println!("  - {}", dep);
-        }
-    }
-    if !pkg.make_depends.is_empty() {
-        println!("\nBuild Dependencies:");
-        for dep in &pkg.make_depends {
-            println!("  - {}", dep);
-        }
-    }
+    print_list("Dependencies", &pkg.depends);
+    print_list("Build Dependencies", &pkg.make_depends);
+    print_list("Check Dependencies", &pkg.check_depends);
+    print_list("Provides", &pkg.provides);
+    print_list("Conflicts", &pkg.conflicts);
+    print_list("Replaces", &pkg.replaces);
     Ok(())
 }
 
-fn cmd_clean() -> Result<(), Box<dyn Error>> {
-    println!("Cleaning build directories...");
-    for entry in fs::read_dir(".")? {
+// Remove cached build directories, keeping the `keep` most recently used ones
+fn cmd_clean(cfg: &Config, keep: usize) -> Result<(), Box<dyn Error>> {
+    let cache = cfg.build_root();
+    println!("Cleaning build directories in {}...", cache.display());
+    let entries = match fs::read_dir(&cache) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!("Nothing to clean");
+            return Ok(());
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+
+    let mut builds = Vec::new();
+    for entry in entries {
         let entry = entry?;
-        if !entry.file_type()?.is_dir() {
+        if !entry.file_type()?.is_dir() || !entry.path().join("PKGBUILD").is_file() {
             continue;
         }
-        let dir_name = entry.file_name().into_string().unwrap();
-        let pkgbuild_path = format!("{}/PKGBUILD", dir_name);
-        if fs::metadata(pkgbuild_path).is_ok() {
-            fs::remove_dir_all(&dir_name)?;
-            println!("Removed: {}", dir_name);
+        // building writes package archives into the dir, so its mtime tracks the last build
+        let modified = entry.metadata()?.modified()?;
+        builds.push((modified, entry.path()));
+    }
+    builds.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    for (_, path) in builds.iter().skip(keep) {
+        if cfg.dry_run {
+            println!("Would remove: {}", path.display());
+            continue;
         }
+        fs::remove_dir_all(path)?;
+        println!("Removed: {}", path.file_name().unwrap_or_default().to_string_lossy());
+    }
+    if keep > 0 {
+        println!("Kept {} most recent build(s)", builds.len().min(keep));
     }
     Ok(())
 }
 
-fn cmd_uninstall(pkgs: &[String], bypass: &bool) -> Result<(), Box<dyn Error>> {
-    check_root(&bypass);
+fn cmd_uninstall(cfg: &Config, pkgs: &[String], bypass: &bool) -> Result<(), Box<dyn Error>> {
+    check_root(bypass);
+    if cfg.dry_run {
+        println!("Dry run, nothing will be changed. These commands would run:");
+    }
 
     for pkg in pkgs {
-        if !prompt_yes(&format!("Really uninstall {}?", pkg)) {
+        let question = format!("Really uninstall {}?", pkg);
+        if !cfg.dry_run && !prompt::confirm(cfg, &question, cfg.prompts.uninstall)? {
             println!("Skipping {}", pkg);
             continue;
         }
-        let status = Shell::new("sudo").arg("pacman").arg("-Rns").arg(pkg).status()?;
-        if status.success() {
-            println!("Successfully removed {}", pkg);
+        let before = installed_versions(&[pkg.as_str()])?;
+        let removed = run_cmd(cfg, Shell::new("sudo").args(["pacman", "-Rns", pkg]))?;
+        if removed {
+            if !cfg.dry_run {
+                println!("Successfully removed {}", pkg);
+            }
         } else {
             eprintln!("Failed to remove {}", pkg);
         }
+        let outcome = match removed {
+            true => history::Outcome::Success,
+            false => history::Outcome::RemoveFailed,
+        };
+        let mut entry = history::Entry::new(history::Action::Remove, pkg, outcome);
+        entry.old_version = before.get(pkg).cloned();
+        history::append(cfg, &[entry]);
+    }
+    Ok(())
+}
+
+// Show the last `limit` history entries, optionally only those of package `pkg`
+fn cmd_history(cfg: &Config, pkg: Option<&str>, limit: usize) -> Result<(), Box<dyn Error>> {
+    let mut entries: Vec<history::Entry> = history::load()?
+        .into_iter()
+        .filter(|e| pkg.is_none_or(|p| e.package == p))
+        .collect();
+    let skip = entries.len().saturating_sub(limit);
+    entries.drain(..skip);
+
+    if cfg.format == OutputFormat::Json {
+        return print_json(&entries);
+    }
+    if entries.is_empty() {
+        println!("No history recorded");
+        return Ok(());
+    }
+    for entry in &entries {
+        let versions = match (&entry.old_version, &entry.new_version) {
+            (Some(old), Some(new)) => format!("{} -> {}", old, new),
+            (Some(ver), None) | (None, Some(ver)) => ver.clone(),
+            (None, None) => String::new(),
+        };
+        let mut source = Vec::new();
+        if let Some(backend) = entry.backend {
+            source.push(if backend == Backend::Github { "github" } else { "aur" }.to_string());
+        }
+        if let Some(commit) = &entry.commit {
+            source.push(commit.chars().take(10).collect());
+        }
+        println!(
+            "{}  {:<9} {} {}{}  {}",
+            history::format_time(entry.time),
+            entry.action.as_str(),
+            entry.package,
+            versions,
+            if source.is_empty() { String::new() } else { format!(" ({})", source.join(" ")) },
+            entry.outcome.as_str()
+        );
+    }
+    Ok(())
+}
+
+// Find the commit of the package base cloned in `dir` whose .SRCINFO has `version`
+fn find_version_commit(
+    dir: &Path,
+    version: &str
+) -> Result<Option<(String, SrcInfo)>, Box<dyn Error>> {
+    let output = Shell::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "--format=%H", "--", ".SRCINFO"])
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("git log failed in {}", dir.display()).into());
+    }
+    for commit in String::from_utf8_lossy(&output.stdout).lines() {
+        let show = Shell::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["show", &format!("{}:.SRCINFO", commit)])
+            .output()?;
+        if !show.status.success() {
+            continue;
+        }
+        let Ok(srcinfo) = SrcInfo::parse(&String::from_utf8_lossy(&show.stdout)) else {
+            continue;
+        };
+        if srcinfo.version().as_deref() == Some(version) {
+            return Ok(Some((commit.to_string(), srcinfo)));
+        }
+    }
+    Ok(None)
+}
+
+// Rebuild `pkg` at `version` from the AUR git history and install it. The clone is put back
+// on its branch afterwards so later updates can pull again.
+fn rebuild_version(cfg: &Config, pkg: &str, version: &str) -> Result<(), Box<dyn Error>> {
+    let base = fetch_info_many(cfg, &[pkg.to_string()])?
+        .first()
+        .map_or(pkg.to_string(), |p| p.base().to_string());
+    if !clone_package(cfg, &base)? {
+        return Err(format!("failed to clone {}", base).into());
+    }
+    let dir = cfg.build_dir(&base);
+    if cfg.dry_run && !dir.is_dir() {
+        println!("  (the commit with {} {} is looked up after cloning)", pkg, version);
+        return Ok(());
+    }
+    let (commit, srcinfo) = find_version_commit(&dir, version)?.ok_or_else(|| {
+        format!("version {} of {} is not in the history of {}", version, pkg, base)
+    })?;
+    println!("Building {} {} from commit {}", pkg, version, &commit[..commit.len().min(10)]);
+
+    let branch = if cfg.use_github() { base.as_str() } else { "master" };
+    let checkout = |rev: &str| {
+        Ok::<_, io::Error>(
+            discard_changes(cfg, &dir)? &&
+                run_cmd(cfg, Shell::new("git").arg("-C").arg(&dir).args(["checkout", "--quiet", rev]))?
+        )
+    };
+    if !checkout(&commit)? {
+        return Err(format!("git checkout {} failed in {}", commit, dir.display()).into());
+    }
+    let unit = deps::BuildUnit {
+        base: base.clone(),
+        pkgs: vec![AurPkg::from_srcinfo(&srcinfo, pkg)],
+        deps: Vec::new(),
+        runtime_deps: Vec::new(),
+    };
+    let resolution = deps::Resolution {
+        build: Vec::new(),
+        explicit: [pkg.to_string()].into_iter().collect(),
+        repo_deps: Vec::new(),
+        missing: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let result = (|| -> Result<(), Box<dyn Error>> {
+        if !cfg.dry_run && !review::review(cfg, &base, &dir)? {
+            return Err("aborted".into());
+        }
+        pgp::check(cfg, &[&base])?;
+        let remove_deps = cfg.remove_make_deps == RemoveMakeDeps::Always;
+        let Some(files) = build_package(cfg, &unit, &resolution, remove_deps)? else {
+            record_build_failure(cfg, &unit);
+            return Err(format!("failed to build {} {}", pkg, version).into());
+        };
+        if !install_packages(cfg, &[(&unit, files)], &resolution)? {
+            return Err(format!("failed to install {} {}", pkg, version).into());
+        }
+        Ok(())
+    })();
+    checkout(branch)?;
+    result
+}
+
+// Install `pkg` at `version` from the archive cache, or rebuild it from the AUR git history.
+// Without a version, the newest cached archive older than the installed version is used.
+fn cmd_downgrade(
+    cfg: &Config,
+    pkg: &str,
+    version: Option<&str>,
+    bypass: &bool
+) -> Result<(), Box<dyn Error>> {
+    check_root(bypass);
+    if let Some(local) = &cfg.local_repo {
+        repo::check(cfg, local)?;
+    }
+    let installed = installed_versions(&[pkg])?.remove(pkg);
+    let cached = archive::cached(cfg, pkg)?;
+    let target = match version {
+        Some(version) => version.to_string(),
+        None => {
+            let is_older = |ver: &str| {
+                installed.as_deref().is_none_or(|i| vercmp(ver, i) == Ordering::Less)
+            };
+            match cached.iter().find(|(ver, _)| is_older(ver)) {
+                Some((ver, _)) => ver.clone(),
+                None => {
+                    return Err(
+                        format!("no older archive of {} is cached; give a version to build", pkg).into()
+                    );
+                }
+            }
+        }
+    };
+
+    let question = format!(
+        "Install {} {} (installed: {})?",
+        pkg,
+        target,
+        installed.as_deref().unwrap_or("none")
+    );
+    if cfg.dry_run {
+        println!("Dry run, nothing will be changed. These commands would run:");
+    } else if !prompt::confirm(cfg, &question, cfg.prompts.proceed)? {
+        println!("Aborting");
+        return Ok(());
+    }
+
+    match cached.iter().find(|(ver, _)| *ver == target) {
+        Some((_, path)) => {
+            let before = installed_versions(&[pkg])?;
+            let ok = install_files(cfg, &[&path.display().to_string()], &[pkg])?;
+            let outcome = match ok {
+                true => history::Outcome::Success,
+                false => history::Outcome::InstallFailed,
+            };
+            let mut entry = history::Entry::new(history::Action::Downgrade, pkg, outcome);
+            entry.old_version = before.get(pkg).cloned();
+            entry.new_version = Some(target.clone());
+            history::append(cfg, &[entry]);
+            if !ok {
+                return Err(format!("failed to install {}", path.display()).into());
+            }
+        }
+        None => {
+            println!("{} {} is not cached; rebuilding it from the AUR git history", pkg, target);
+            rebuild_version(cfg, pkg, &target)?;
+        }
+    }
+    if !cfg.dry_run {
+        println!("Installed {} {}", pkg, target);
+        println!("Add {} to `hold` in the config to keep update from upgrading it again", pkg);
     }
     Ok(())
 }
 
+// Go back to the version `pkg` had before its last update
+fn cmd_rollback(cfg: &Config, pkg: &str, bypass: &bool) -> Result<(), Box<dyn Error>> {
+    let last = history::load()?
+        .into_iter()
+        .rev()
+        .find(|e| {
+            e.package == pkg &&
+                e.outcome == history::Outcome::Success &&
+                matches!(e.action, history::Action::Update | history::Action::Downgrade)
+        });
+    let version = last
+        .and_then(|e| e.old_version)
+        .ok_or_else(|| format!("no recorded update of {} to roll back", pkg))?;
+    cmd_downgrade(cfg, pkg, Some(&version), bypass)
+}
+
+fn cmd_sync_index(cfg: &Config, file: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    match file {
+        Some(path) => println!("Reading {}...", path.display()),
+        None => println!("Downloading {}...", cfg.endpoints.aur_meta),
+    }
+    let count = index::sync(cfg, file)?;
+    println!("Indexed {} packages in {}", count, index::index_path().display());
+    Ok(())
+}
+
+fn cmd_config_show(cfg: &Config) -> Result<(), Box<dyn Error>> {
+    if cfg.sources.is_empty() {
+        println!("# no config files found, using defaults");
+    }
+    for path in &cfg.sources {
+        println!("# loaded {}", path.display());
+    }
+    println!("# user config: {}", config::user_config_path().display());
+    println!("# build directory: {}", cfg.build_root().display());
+    print!("{}", cfg.to_toml()?);
+    Ok(())
+}
+
 fn check_root(bypass: &bool) {
     // Check is user is root         Check if a bypass flag was provided
     if Uid::effective().is_root() && !*bypass {
@@ -519,6 +1667,74 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .global(true)
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .help("Use the index from sync-index instead of the AUR RPC")
+                .global(true)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("yes")
+                .long("yes")
+                .help("Answer yes to every question")
+                .global(true)
+                .conflicts_with_all(["no", "noconfirm"])
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("no")
+                .long("no")
+                .help("Answer no to every question")
+                .global(true)
+                .conflicts_with("noconfirm")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("noconfirm")
+                .long("noconfirm")
+                .help("Answer every question with its configured default")
+                .global(true)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Print what install, update, uninstall and clean would do without doing it")
+                .global(true)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("chroot")
+                .long("chroot")
+                .help("Build in a clean devtools chroot instead of on the host")
+                .global(true)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("transaction")
+                .long("transaction")
+                .help("Build all packages first and install them together only if every build succeeds")
+                .global(true)
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format for search, info and update --check")
+                .value_parser(["text", "json"])
+                .global(true)
+        )
+        .args(
+            config::ENDPOINT_ENV.iter().map(|(name, var)| {
+                Arg::new(*name)
+                    .long(*name)
+                    .value_name("URL")
+                    .help(format!("Override the {} endpoint (env: {})", name, var))
+                    .global(true)
+            })
+        )
         .subcommand_required(false)
         .subcommand(
             Command::new("search")
@@ -535,13 +1751,56 @@ fn main() -> Result<(), Box<dyn Error>> {
                 )
                 .alias("i")
         )
-        .subcommand(Command::new("update").about("Update installed AUR packages").alias("u"))
+        .subcommand(
+            Command::new("update")
+                .about("Update installed AUR packages")
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Only report available updates (implied by --format json)")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .value_name("N")
+                        .help("Number of concurrent update checks")
+                        .value_parser(clap::value_parser!(usize))
+                )
+                .arg(
+                    Arg::new("ignore")
+                        .long("ignore")
+                        .value_name("PKG,...")
+                        .help("Don't check or update these packages")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                )
+                .arg(
+                    Arg::new("devel")
+                        .long("devel")
+                        .help("Also check VCS (-git, -svn, -hg) packages for new upstream commits")
+                        .action(ArgAction::SetTrue)
+                )
+                .alias("u")
+        )
         .subcommand(
             Command::new("info")
                 .about("Show package information")
                 .arg(Arg::new("package").required(true))
         )
-        .subcommand(Command::new("clean").about("Clean build directories"))
+        .subcommand(
+            Command::new("clean")
+                .about("Clean cached build directories")
+                .arg(
+                    Arg::new("keep")
+                        .long("keep")
+                        .value_name("N")
+                        .help("Keep the N most recently built packages")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0")
+                )
+        )
         .subcommand(
             Command::new("uninstall")
                 .about("Uninstall AUR packages")
@@ -552,6 +1811,54 @@ fn main() -> Result<(), Box<dyn Error>> {
                 )
                 .alias("r")
         )
+        .subcommand(
+            Command::new("rollback")
+                .about("Reinstall the version a package had before its last update")
+                .arg(Arg::new("package").required(true))
+        )
+        .subcommand(
+            Command::new("downgrade")
+                .about("Install another version of a package from the archive cache or AUR history")
+                .arg(Arg::new("package").required(true))
+                .arg(
+                    Arg::new("version").help(
+                        "[epoch:]pkgver-pkgrel (default: the newest older archive in the cache)"
+                    )
+                )
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show what raur installed, updated and removed")
+                .arg(Arg::new("package").help("Only show this package"))
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .short('n')
+                        .value_name("N")
+                        .help("Show the last N entries")
+                        .default_value("20")
+                        .value_parser(clap::value_parser!(usize))
+                )
+        )
+        .subcommand(
+            Command::new("sync-index")
+                .about("Download the AUR metadata dump for offline use")
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("PATH")
+                        .help(format!("Read a local {} instead of downloading it", index::DUMP_NAME))
+                        .value_parser(clap::value_parser!(std::path::PathBuf))
+                )
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show").about("Print the effective configuration")
+                )
+        )
         .get_matches();
 
     if matches.get_flag("meow") {
@@ -559,8 +1866,6 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
-    let use_github = matches.get_flag("github");
-
     if matches.subcommand().is_none() {
         eprintln!("error: 'raur' requires a subcommand but one was not provided");
         eprintln!("\nFor more information, try '--help'.");
@@ -569,9 +1874,47 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let bypass = matches.get_flag("bypass-sudo");
 
+    let mut cfg = Config::load()?;
+    // command line flags override the config files
+    if matches.get_flag("github") {
+        cfg.backend = Backend::Github;
+    }
+    if matches.get_flag("offline") {
+        cfg.offline = true;
+    }
+    if matches.get_flag("yes") {
+        cfg.answer = Answer::Yes;
+    } else if matches.get_flag("no") {
+        cfg.answer = Answer::No;
+    } else if matches.get_flag("noconfirm") {
+        cfg.answer = Answer::Default;
+    }
+    if matches.get_flag("dry-run") {
+        cfg.dry_run = true;
+    }
+    if matches.get_flag("chroot") {
+        cfg.chroot = true;
+    }
+    if matches.get_flag("transaction") {
+        cfg.transaction = true;
+    }
+    match matches.get_one::<String>("format").map(|s| s.as_str()) {
+        Some("json") => {
+            cfg.format = OutputFormat::Json;
+        }
+        Some("text") => {
+            cfg.format = OutputFormat::Text;
+        }
+        _ => {}
+    }
+    for (name, _) in config::ENDPOINT_ENV {
+        if let Some(url) = matches.get_one::<String>(name) {
+            cfg.endpoints.set(name, url.clone());
+        }
+    }
+
     match matches.subcommand() {
-        Some(("search", sub_m)) =>
-            cmd_search(sub_m.get_one::<String>("query").unwrap(), use_github)?,
+        Some(("search", sub_m)) => cmd_search(&cfg, sub_m.get_one::<String>("query").unwrap())?,
         Some(("install", sub_m)) => {
             check_root(&bypass);
 
@@ -580,19 +1923,51 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .unwrap()
                 .cloned()
                 .collect();
-            cmd_install(&packages, use_github)?;
+            cmd_install(&cfg, &packages)?;
         }
-        Some(("update", _)) => cmd_update(use_github, &bypass)?,
-        Some(("info", sub_m)) => cmd_info(sub_m.get_one::<String>("package").unwrap(), use_github)?,
-        Some(("clean", _)) => cmd_clean()?,
+        Some(("update", sub_m)) => {
+            if let Some(jobs) = sub_m.get_one::<usize>("jobs") {
+                cfg.jobs = *jobs;
+            }
+            if sub_m.get_flag("devel") {
+                cfg.devel = true;
+            }
+            if let Some(ignore) = sub_m.get_many::<String>("ignore") {
+                cfg.ignore.extend(ignore.cloned());
+            }
+            cmd_update(&cfg, &bypass, sub_m.get_flag("check"))?
+        }
+        Some(("info", sub_m)) => cmd_info(&cfg, sub_m.get_one::<String>("package").unwrap())?,
+        Some(("clean", sub_m)) => cmd_clean(&cfg, *sub_m.get_one::<usize>("keep").unwrap())?,
         Some(("uninstall", sub_m)) => {
             let packages: Vec<String> = sub_m
                 .get_many::<String>("packages")
                 .unwrap()
                 .cloned()
                 .collect();
-            cmd_uninstall(&packages, &bypass)?;
+            cmd_uninstall(&cfg, &packages, &bypass)?;
+        }
+        Some(("rollback", sub_m)) => {
+            cmd_rollback(&cfg, sub_m.get_one::<String>("package").unwrap(), &bypass)?
+        }
+        Some(("downgrade", sub_m)) => {
+            let pkg = sub_m.get_one::<String>("package").unwrap();
+            let version = sub_m.get_one::<String>("version").map(|s| s.as_str());
+            cmd_downgrade(&cfg, pkg, version, &bypass)?
         }
+        Some(("history", sub_m)) => {
+            let pkg = sub_m.get_one::<String>("package").map(|s| s.as_str());
+            cmd_history(&cfg, pkg, *sub_m.get_one::<usize>("limit").unwrap())?
+        }
+        Some(("sync-index", sub_m)) => {
+            let file = sub_m.get_one::<std::path::PathBuf>("file");
+            cmd_sync_index(&cfg, file.map(|f| f.as_path()))?
+        }
+        Some(("config", sub_m)) =>
+            match sub_m.subcommand() {
+                Some(("show", _)) => cmd_config_show(&cfg)?,
+                _ => unreachable!(),
+            }
         _ => unreachable!(),
     }
     Ok(())
@@ -0,0 +1,147 @@
+// pacmanconf.rs - the parts of pacman.conf(5) raur cares about: IgnorePkg, IgnoreGroup and the
+// names of the configured repositories
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process::Command as Shell;
+
+#[derive(Default)]
+pub struct PacmanConf {
+    pub ignore_pkg: Vec<String>,
+    pub ignore_group: Vec<String>,
+    // repository sections, in order
+    pub repos: Vec<String>,
+}
+
+impl PacmanConf {
+    // Read `path` and the files its [options] section includes. A missing file is empty.
+    pub fn load(path: &Path) -> Result<PacmanConf, Box<dyn Error>> {
+        let mut conf = PacmanConf::default();
+        conf.read(path)?;
+        Ok(conf)
+    }
+
+    fn read(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(format!("cannot read {}: {}", path.display(), e).into());
+            }
+        };
+
+        let mut in_options = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                in_options = section == "options";
+                if !in_options {
+                    self.repos.push(section.to_string());
+                }
+                continue;
+            }
+            if !in_options {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let values = value.split_whitespace().map(|v| v.to_string());
+            match key.trim() {
+                "IgnorePkg" => self.ignore_pkg.extend(values),
+                "IgnoreGroup" => self.ignore_group.extend(values),
+                "Include" => {
+                    for include in value.split_whitespace() {
+                        self.read(&PathBuf::from(include))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // The reason `name` is held back, if any. `groups` maps IgnoreGroup entries to their members.
+    pub fn held_by(&self, name: &str, groups: &[(String, String)]) -> Option<String> {
+        if self.ignore_pkg.iter().any(|pattern| glob_match(pattern, name)) {
+            return Some("IgnorePkg".to_string());
+        }
+        groups
+            .iter()
+            .find(|(_, pkg)| pkg == name)
+            .map(|(group, _)| format!("IgnoreGroup {}", group))
+    }
+
+    // (group, package) pairs for the installed members of the ignored groups
+    pub fn ignored_group_members(&self) -> Vec<(String, String)> {
+        if self.ignore_group.is_empty() {
+            return Vec::new();
+        }
+        let output = match Shell::new("pacman").arg("-Qg").args(&self.ignore_group).output() {
+            Ok(output) => output,
+            Err(_) => {
+                return Vec::new();
+            }
+        };
+        // pacman -Qg fails if one of the groups has no installed members; the rest is still printed
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(group, pkg)| (group.to_string(), pkg.to_string()))
+            .collect()
+    }
+}
+
+// Shell-style pattern match supporting '*' and '?', as pacman does for IgnorePkg
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last '*' and the name position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_patterns() {
+        let cases = [
+            ("foo", "foo", true),
+            ("foo", "foobar", false),
+            ("foo*", "foo", true),
+            ("foo*", "foo-git", true),
+            ("*-git", "foo-git", true),
+            ("*-git", "foo-git-bin", false),
+            ("lib?2", "lib32", true),
+            ("lib?2", "lib2", false),
+            ("*o*o*", "foobar", true),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYbZ", false),
+            ("*", "", true),
+            ("?", "", false),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(glob_match(pattern, name), expected, "{} ~ {}", pattern, name);
+        }
+    }
+}
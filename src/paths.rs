@@ -0,0 +1,38 @@
+// paths.rs - per-user directories used by raur (XDG base directories)
+use std::env;
+use std::path::{ Path, PathBuf };
+
+fn home_dir() -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/"))
+}
+
+// $<var> if it is set to an absolute path, otherwise $HOME/<fallback>
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match env::var_os(var).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => home_dir().join(fallback),
+    }
+}
+
+// Persistent state that isn't configuration (review records, ...): $XDG_STATE_HOME/raur
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state").join("raur")
+}
+
+// Build cache with one git clone per package base: $XDG_CACHE_HOME/raur
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache").join("raur")
+}
+
+// $XDG_CONFIG_HOME/raur
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("raur")
+}
+
+// Expand a leading "~/" in paths coming from config files
+pub fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home_dir().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,120 @@
+// pgp.rs - keys that signed sources are verified against (validpgpkeys in .SRCINFO)
+// makepkg only checks signatures against the user's keyring, so missing keys are imported
+// before building instead of failing with "unknown public key".
+use std::error::Error;
+use std::io;
+use std::path::PathBuf;
+use std::process::{ Command as Shell, Stdio };
+
+use crate::config::Config;
+use crate::paths::expand_home;
+use crate::prompt;
+use crate::read_srcinfo;
+use crate::run_cmd;
+
+// fingerprint and the package bases whose sources it signs
+type MissingKey = (String, Vec<String>);
+
+fn has_key(fingerprint: &str) -> bool {
+    Shell::new("gpg")
+        .args(["--list-keys", fingerprint])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+// validpgpkeys entries of `bases` that are missing from the keyring
+fn missing_keys(cfg: &Config, bases: &[&str]) -> Result<Vec<MissingKey>, Box<dyn Error>> {
+    let mut missing: Vec<MissingKey> = Vec::new();
+    for base in bases {
+        let srcinfo = match read_srcinfo(&cfg.build_dir(base)) {
+            Ok(srcinfo) => srcinfo,
+            // a dry run may not have cloned anything yet
+            Err(_) if cfg.dry_run => {
+                continue;
+            }
+            Err(e) => {
+                return Err(format!("cannot read .SRCINFO of {}: {}", base, e).into());
+            }
+        };
+        for key in srcinfo.base_values("validpgpkeys") {
+            let key = key.to_uppercase();
+            if let Some((_, needed_by)) = missing.iter_mut().find(|(k, _)| *k == key) {
+                needed_by.push(base.to_string());
+            } else if !has_key(&key) {
+                missing.push((key, vec![base.to_string()]));
+            }
+        }
+    }
+    Ok(missing)
+}
+
+// Offer to import the keys the packages of `bases` are signed with and the keyring lacks.
+// Declining is not an error: makepkg reports the failed signature check itself.
+pub fn check(cfg: &Config, bases: &[&str]) -> Result<(), Box<dyn Error>> {
+    let missing = missing_keys(cfg, bases)?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+    println!("\nMissing PGP keys:");
+    for (key, needed_by) in &missing {
+        println!("  {}  (needed by {})", key, needed_by.join(", "));
+    }
+
+    let keyring = cfg.keyring.as_deref().map(expand_home);
+    let from = match (&keyring, &cfg.keyserver) {
+        (Some(file), _) => file.display().to_string(),
+        (None, Some(server)) => server.clone(),
+        (None, None) => "the default keyserver".to_string(),
+    };
+    let import = if cfg.dry_run {
+        cfg.prompts.import_keys
+    } else {
+        prompt::confirm(cfg, &format!("Import them from {}?", from), cfg.prompts.import_keys)?
+    };
+    if !import {
+        println!("Not importing; building packages with signed sources will fail");
+        return Ok(());
+    }
+
+    let keys: Vec<&str> = missing.iter().map(|(key, _)| key.as_str()).collect();
+    let imported = import_keys(cfg, keyring, &keys).map_err(|e| -> Box<dyn Error> {
+        if e.kind() == io::ErrorKind::NotFound {
+            "importing PGP keys needs gpg (install gnupg)".into()
+        } else {
+            e.into()
+        }
+    })?;
+    if !imported {
+        return Err("importing PGP keys failed".into());
+    }
+    if cfg.dry_run {
+        return Ok(());
+    }
+    let still_missing: Vec<&str> = keys
+        .into_iter()
+        .filter(|key| !has_key(key))
+        .collect();
+    if !still_missing.is_empty() {
+        eprintln!("Still missing after the import: {}", still_missing.join(", "));
+    }
+    Ok(())
+}
+
+// A key file is imported whole; a keyserver is only asked for `keys`
+fn import_keys(cfg: &Config, keyring: Option<PathBuf>, keys: &[&str]) -> io::Result<bool> {
+    let mut gpg = Shell::new("gpg");
+    match keyring {
+        Some(file) => {
+            gpg.arg("--import").arg(file);
+        }
+        None => {
+            if let Some(server) = &cfg.keyserver {
+                gpg.args(["--keyserver", server]);
+            }
+            gpg.arg("--recv-keys").args(keys);
+        }
+    }
+    run_cmd(cfg, &mut gpg)
+}
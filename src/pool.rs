@@ -0,0 +1,37 @@
+// pool.rs - bounded worker pool for running blocking network requests concurrently
+use std::sync::Mutex;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::thread;
+
+// Apply `f` to every item using at most `jobs` threads. Results keep the order of `items`.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+    where T: Sync, R: Send, F: Fn(&T) -> R + Sync
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= items.len() {
+                        break;
+                    }
+                    let result = f(&items[i]);
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("worker finished without a result"))
+        .collect()
+}
@@ -0,0 +1,119 @@
+// prompt.rs - yes/no questions, answered on the terminal or by --yes/--no/--noconfirm
+use std::error::Error;
+use std::io::{ self, IsTerminal, Write };
+
+use crate::config::{ Answer, Config };
+
+// True if questions are answered without reading from the terminal
+pub fn is_automatic(cfg: &Config) -> bool {
+    cfg.answer != Answer::Ask
+}
+
+// Ask a yes/no question; an empty answer picks `default`. Fails instead of guessing when nobody
+// can answer: stdin is not a terminal or is closed, and no --yes/--no/--noconfirm was given.
+pub fn confirm(cfg: &Config, question: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    let (answer, reason) = match cfg.answer {
+        Answer::Yes => (true, "--yes"),
+        Answer::No => (false, "--no"),
+        Answer::Default => (default, "--noconfirm"),
+        Answer::Ask => {
+            return ask(question, hint, default);
+        }
+    };
+    println!("{} {} {} ({})", question, hint, if answer { "y" } else { "n" }, reason);
+    Ok(answer)
+}
+
+// Pick one of `options` by number; an empty answer picks `default` (0-based). Any of
+// --yes/--no/--noconfirm picks the default without asking.
+pub fn choose(
+    cfg: &Config,
+    question: &str,
+    options: &[String],
+    default: usize
+) -> Result<usize, Box<dyn Error>> {
+    for (i, option) in options.iter().enumerate() {
+        println!("  {}. {}", i + 1, option);
+    }
+    let hint = format!("[1-{}, default {}]", options.len(), default + 1);
+    let reason = match cfg.answer {
+        Answer::Yes => "--yes",
+        Answer::No => "--no",
+        Answer::Default => "--noconfirm",
+        Answer::Ask => {
+            return ask_number(question, &hint, options.len(), default);
+        }
+    };
+    println!("{} {} {} ({})", question, hint, default + 1, reason);
+    Ok(default)
+}
+
+fn ask_number(
+    question: &str,
+    hint: &str,
+    count: usize,
+    default: usize
+) -> Result<usize, Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(
+            format!(
+                "cannot ask \"{}\": stdin is not a terminal; use --yes, --no or --noconfirm",
+                question
+            ).into()
+        );
+    }
+    loop {
+        print!("{} {} ", question, hint);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            println!();
+            return Err(format!("no answer to \"{}\" (end of input)", question).into());
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(default);
+        }
+        match input.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => {
+                return Ok(n - 1);
+            }
+            _ => println!("Please enter a number from 1 to {}.", count),
+        }
+    }
+}
+
+fn ask(question: &str, hint: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(
+            format!(
+                "cannot ask \"{}\": stdin is not a terminal; use --yes, --no or --noconfirm",
+                question
+            ).into()
+        );
+    }
+    loop {
+        print!("{} {} ", question, hint);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            println!();
+            return Err(format!("no answer to \"{}\" (end of input)", question).into());
+        }
+        match input.trim().to_lowercase().as_str() {
+            "" => {
+                return Ok(default);
+            }
+            "y" | "yes" => {
+                return Ok(true);
+            }
+            "n" | "no" => {
+                return Ok(false);
+            }
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
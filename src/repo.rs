@@ -0,0 +1,74 @@
+// repo.rs - local pacman repository that built packages are added to and installed from
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command as Shell;
+
+use crate::config::{ Config, LocalRepo };
+use crate::pacmanconf::PacmanConf;
+use crate::paths;
+use crate::run_cmd;
+
+impl LocalRepo {
+    pub fn dir(&self) -> PathBuf {
+        paths::expand_home(&self.dir)
+    }
+
+    fn db_path(&self) -> PathBuf {
+        self.dir().join(format!("{}.db.tar.gz", self.name))
+    }
+
+    // pacman.conf section that serves the repository
+    pub fn section(&self) -> String {
+        format!(
+            "[{}]\nSigLevel = Optional TrustAll\nServer = file://{}\n",
+            self.name,
+            self.dir().display()
+        )
+    }
+}
+
+// Fail early if pacman doesn't know the repository, since packages are installed from it
+pub fn check(cfg: &Config, repo: &LocalRepo) -> Result<(), Box<dyn Error>> {
+    if PacmanConf::load(&cfg.pacman_conf)?.repos.contains(&repo.name) {
+        return Ok(());
+    }
+    eprintln!("Add this section to {}:\n\n{}", cfg.pacman_conf.display(), repo.section());
+    Err(format!("the local repository '{}' is not in {}", repo.name, cfg.pacman_conf.display()).into())
+}
+
+// Copy built archives into the repository and add them to its database. repo-add -R deletes
+// the archives of the versions they replace.
+pub fn add(cfg: &Config, repo: &LocalRepo, files: &[&String]) -> Result<bool, Box<dyn Error>> {
+    let dir = repo.dir();
+    if !cfg.dry_run {
+        fs::create_dir_all(&dir)?;
+    }
+    if !run_cmd(cfg, Shell::new("cp").args(files).arg(&dir))? {
+        return Ok(false);
+    }
+    let added: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|f| PathBuf::from(f).file_name().map(|name| dir.join(name)))
+        .collect();
+    Ok(run_cmd(cfg, Shell::new("repo-add").arg("-R").arg(repo.db_path()).args(&added))?)
+}
+
+// Refresh only the local repository's database and install `names` from it. Syncing with a
+// config that lists nothing but this repository avoids a partial upgrade of the system.
+pub fn install(cfg: &Config, repo: &LocalRepo, names: &[&str]) -> Result<bool, Box<dyn Error>> {
+    let conf = cfg.build_root().join(format!("{}.pacman.conf", repo.name));
+    if !cfg.dry_run {
+        fs::create_dir_all(cfg.build_root())?;
+        fs::write(&conf, format!("[options]\n{}", repo.section()))?;
+    }
+    let synced = run_cmd(cfg, Shell::new("sudo").args(["pacman", "-Sy", "--config"]).arg(&conf))?;
+    if !synced {
+        return Ok(false);
+    }
+    let targets: Vec<String> = names
+        .iter()
+        .map(|n| format!("{}/{}", repo.name, n))
+        .collect();
+    Ok(run_cmd(cfg, Shell::new("sudo").args(["pacman", "-S", "--noconfirm"]).args(&targets))?)
+}
@@ -0,0 +1,126 @@
+// review.rs - show build files before building and remember the last reviewed revision
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{ Path, PathBuf };
+use std::process::{ Command as Shell, Stdio };
+
+use crate::config::{ Config, ReviewPolicy };
+use crate::paths::state_dir;
+use crate::prompt;
+
+fn reviewed_file(base: &str) -> PathBuf {
+    state_dir().join("reviewed").join(base)
+}
+
+// Commit hash of the revision last approved for package base `base`
+fn last_reviewed(base: &str) -> Option<String> {
+    fs::read_to_string(reviewed_file(base))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn save_reviewed(base: &str, rev: &str) -> Result<(), Box<dyn Error>> {
+    let path = reviewed_file(base);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, format!("{}\n", rev))?;
+    Ok(())
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Result<Option<String>, Box<dyn Error>> {
+    let output = Shell::new("git").arg("-C").arg(dir).args(args).stderr(Stdio::null()).output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+// Full text of the PKGBUILD and any .install scripts, plus a list of everything else in the repo
+fn full_text(dir: &Path) -> Result<String, Box<dyn Error>> {
+    let mut text = String::new();
+    let files = git_output(dir, &["ls-files"])?.unwrap_or_default();
+    text.push_str("==> Files in this package:\n");
+    for file in files.lines() {
+        text.push_str(&format!("    {}\n", file));
+    }
+
+    let mut shown: Vec<&str> = files
+        .lines()
+        .filter(|f| *f == "PKGBUILD" || f.ends_with(".install"))
+        .collect();
+    shown.sort_by_key(|f| *f != "PKGBUILD");
+    for file in shown {
+        let content = fs::read_to_string(dir.join(file))?;
+        text.push_str(&format!("\n==> {}\n{}", file, content));
+    }
+    Ok(text)
+}
+
+// Show `text` in $PAGER (less by default), falling back to plain stdout
+fn page(text: &str) -> Result<(), Box<dyn Error>> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut parts = pager.split_whitespace();
+    let child = match parts.next() {
+        Some(cmd) => Shell::new(cmd).args(parts).stdin(Stdio::piped()).spawn(),
+        None => Err(std::io::ErrorKind::NotFound.into()),
+    };
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // the pager may be closed before reading everything
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+        }
+        Err(_) => {
+            println!("{}", text);
+        }
+    }
+    Ok(())
+}
+
+// Review the build files cloned into `dir` for package base `base`.
+// With the diff policy only the changes since the last approved revision are shown.
+// Returns false if the user doesn't want to build it.
+pub fn review(cfg: &Config, base: &str, dir: &Path) -> Result<bool, Box<dyn Error>> {
+    if cfg.review == ReviewPolicy::Skip {
+        return Ok(true);
+    }
+    let head = git_output(dir, &["rev-parse", "HEAD"])?
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| format!("cannot determine git revision of {}", dir.display()))?;
+
+    let last = if cfg.review == ReviewPolicy::Diff { last_reviewed(base) } else { None };
+    if last.as_deref() == Some(head.as_str()) {
+        println!("{}: build files unchanged since last review", base);
+        return Ok(true);
+    }
+
+    let diff = match &last {
+        Some(rev) => git_output(dir, &["diff", rev, &head])?,
+        None => None,
+    };
+    let text = match diff {
+        Some(diff) => {
+            let rev = last.as_deref().unwrap_or_default();
+            format!("==> Changes in {} since last review ({})\n{}", base, rev, diff)
+        }
+        // never reviewed, or the old revision is gone from the history
+        None => full_text(dir)?,
+    };
+
+    let question = format!("Build {} with these build files?", base);
+    // nobody reads the files when the answer is given up front, so they don't count as reviewed
+    if prompt::is_automatic(cfg) {
+        return prompt::confirm(cfg, &question, cfg.prompts.review);
+    }
+    page(&text)?;
+    if !prompt::confirm(cfg, &question, cfg.prompts.review)? {
+        return Ok(false);
+    }
+    save_reviewed(base, &head)?;
+    Ok(true)
+}
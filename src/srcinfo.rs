@@ -0,0 +1,272 @@
+// srcinfo.rs - parser for the .SRCINFO metadata file generated by makepkg --printsrcinfo
+use std::collections::HashMap;
+use std::error::Error;
+
+// Keys a pkgname section is allowed to override (see PKGBUILD(5), "package splitting")
+const PACKAGE_KEYS: &[&str] = &[
+    "pkgdesc",
+    "arch",
+    "url",
+    "license",
+    "groups",
+    "depends",
+    "optdepends",
+    "provides",
+    "conflicts",
+    "replaces",
+    "backup",
+    "options",
+    "install",
+    "changelog",
+];
+
+// key -> values, where arch-specific arrays keep their suffix ("depends_x86_64")
+type Fields = HashMap<String, Vec<String>>;
+
+pub struct SrcInfo {
+    pub pkgbase: String,
+    base: Fields,
+    packages: Vec<(String, Fields)>,
+}
+
+// Architecture used to pick arch-specific arrays, as named by makepkg
+pub fn host_arch() -> &'static str {
+    std::env::consts::ARCH
+}
+
+fn is_package_key(key: &str) -> bool {
+    PACKAGE_KEYS.iter().any(|k| {
+        key == *k || key.strip_prefix(k).is_some_and(|rest| rest.starts_with('_'))
+    })
+}
+
+impl SrcInfo {
+    pub fn parse(text: &str) -> Result<SrcInfo, Box<dyn Error>> {
+        let mut pkgbase: Option<String> = None;
+        let mut base = Fields::new();
+        let mut packages: Vec<(String, Fields)> = Vec::new();
+
+        for (lineno, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((k, v)) => (k.trim(), v.trim()),
+                None => {
+                    return Err(format!(".SRCINFO line {}: expected 'key = value'", lineno + 1).into());
+                }
+            };
+
+            match key {
+                "pkgbase" => {
+                    if pkgbase.is_some() {
+                        return Err(format!(".SRCINFO line {}: duplicate pkgbase", lineno + 1).into());
+                    }
+                    pkgbase = Some(value.to_string());
+                }
+                "pkgname" => {
+                    packages.push((value.to_string(), Fields::new()));
+                }
+                _ => {
+                    if pkgbase.is_none() {
+                        return Err(
+                            format!(".SRCINFO line {}: '{}' before pkgbase", lineno + 1, key).into()
+                        );
+                    }
+                    let section = match packages.last_mut() {
+                        Some((_, fields)) => {
+                            if !is_package_key(key) {
+                                return Err(
+                                    format!(
+                                        ".SRCINFO line {}: '{}' cannot be set per package",
+                                        lineno + 1,
+                                        key
+                                    ).into()
+                                );
+                            }
+                            fields
+                        }
+                        None => &mut base,
+                    };
+                    let values = section.entry(key.to_string()).or_default();
+                    // an empty value in a package section clears the inherited array
+                    if !value.is_empty() {
+                        values.push(value.to_string());
+                    }
+                }
+            }
+        }
+
+        let pkgbase = pkgbase.ok_or(".SRCINFO has no pkgbase")?;
+        if packages.is_empty() {
+            return Err(".SRCINFO has no pkgname".into());
+        }
+        Ok(SrcInfo { pkgbase, base, packages })
+    }
+
+    fn first(&self, key: &str) -> Option<&str> {
+        self.base
+            .get(key)
+            .and_then(|v| v.first())
+            .map(|s| s.as_str())
+    }
+
+    pub fn pkgver(&self) -> Option<&str> {
+        self.first("pkgver")
+    }
+
+    pub fn pkgrel(&self) -> Option<&str> {
+        self.first("pkgrel")
+    }
+
+    pub fn epoch(&self) -> Option<&str> {
+        self.first("epoch").filter(|e| *e != "0")
+    }
+
+    // Full version as pacman reports it: [epoch:]pkgver-pkgrel
+    pub fn version(&self) -> Option<String> {
+        let ver = match self.pkgrel() {
+            Some(rel) => format!("{}-{}", self.pkgver()?, rel),
+            None => self.pkgver()?.to_string(),
+        };
+        match self.epoch() {
+            Some(epoch) => Some(format!("{}:{}", epoch, ver)),
+            None => Some(ver),
+        }
+    }
+
+    // Values of a pkgbase-level array, including the variant for the host architecture
+    pub fn base_values(&self, key: &str) -> Vec<String> {
+        arch_values(&self.base, key)
+    }
+
+    // Values of an array for one sub-package: a pkgname section overrides the pkgbase value
+    pub fn pkg_values(&self, pkgname: &str, key: &str) -> Vec<String> {
+        let fields = self.packages
+            .iter()
+            .find(|(name, _)| name == pkgname)
+            .map(|(_, f)| f);
+        let arch_key = format!("{}_{}", key, host_arch());
+        let pick = |k: &str| -> Vec<String> {
+            match fields.and_then(|f| f.get(k)) {
+                Some(v) => v.clone(),
+                None => self.base.get(k).cloned().unwrap_or_default(),
+            }
+        };
+        let mut values = pick(key);
+        values.extend(pick(&arch_key));
+        values
+    }
+
+    pub fn pkgdesc(&self, pkgname: &str) -> Option<String> {
+        self.pkg_values(pkgname, "pkgdesc").into_iter().next()
+    }
+
+    pub fn depends(&self, pkgname: &str) -> Vec<String> {
+        self.pkg_values(pkgname, "depends")
+    }
+
+    pub fn make_depends(&self) -> Vec<String> {
+        self.base_values("makedepends")
+    }
+
+    pub fn check_depends(&self) -> Vec<String> {
+        self.base_values("checkdepends")
+    }
+
+    pub fn provides(&self, pkgname: &str) -> Vec<String> {
+        self.pkg_values(pkgname, "provides")
+    }
+
+    pub fn conflicts(&self, pkgname: &str) -> Vec<String> {
+        self.pkg_values(pkgname, "conflicts")
+    }
+
+    pub fn replaces(&self, pkgname: &str) -> Vec<String> {
+        self.pkg_values(pkgname, "replaces")
+    }
+}
+
+fn arch_values(fields: &Fields, key: &str) -> Vec<String> {
+    let mut values = fields.get(key).cloned().unwrap_or_default();
+    if let Some(arch) = fields.get(&format!("{}_{}", key, host_arch())) {
+        values.extend(arch.iter().cloned());
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_package() -> SrcInfo {
+        let arch = host_arch();
+        let text = format!(
+            "# Generated by makepkg\n\
+             pkgbase = foo\n\
+             \tpkgdesc = Base description\n\
+             \tpkgver = 1.2\n\
+             \tpkgrel = 3\n\
+             \tepoch = 1\n\
+             \tmakedepends = cmake\n\
+             \tcheckdepends = python\n\
+             \tdepends = glibc\n\
+             \tdepends_{arch} = lib32-glibc\n\
+             \tsource = git+https://example.com/foo.git\n\
+             \n\
+             pkgname = foo\n\
+             \tprovides = foo-bin=1.2\n\
+             \n\
+             pkgname = foo-docs\n\
+             \tpkgdesc = Documentation\n\
+             \tdepends = \n\
+             \tconflicts = foo-docs-git\n"
+        );
+        SrcInfo::parse(&text).unwrap()
+    }
+
+    #[test]
+    fn base_fields() {
+        let info = split_package();
+        assert_eq!(info.pkgbase, "foo");
+        assert_eq!(info.version().as_deref(), Some("1:1.2-3"));
+        assert_eq!(info.make_depends(), ["cmake"]);
+        assert_eq!(info.check_depends(), ["python"]);
+        assert_eq!(info.base_values("source"), ["git+https://example.com/foo.git"]);
+    }
+
+    #[test]
+    fn packages_inherit_and_override() {
+        let info = split_package();
+        assert_eq!(info.depends("foo"), ["glibc", "lib32-glibc"]);
+        assert_eq!(info.pkgdesc("foo").as_deref(), Some("Base description"));
+        assert_eq!(info.provides("foo"), ["foo-bin=1.2"]);
+        // an empty value clears the inherited array; the arch-specific one is still inherited
+        assert_eq!(info.depends("foo-docs"), ["lib32-glibc"]);
+        assert_eq!(info.pkgdesc("foo-docs").as_deref(), Some("Documentation"));
+        assert_eq!(info.conflicts("foo-docs"), ["foo-docs-git"]);
+        assert!(info.conflicts("foo").is_empty());
+    }
+
+    #[test]
+    fn epoch_zero_is_omitted() {
+        let info = SrcInfo::parse("pkgbase = a\n\tpkgver = 1\n\tpkgrel = 1\n\tepoch = 0\npkgname = a\n")
+            .unwrap();
+        assert_eq!(info.version().as_deref(), Some("1-1"));
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        for text in [
+            "pkgname = a\n",
+            "pkgbase = a\n",
+            "pkgbase = a\npkgbase = b\npkgname = a\n",
+            "pkgdesc = x\npkgbase = a\npkgname = a\n",
+            "pkgbase = a\npkgname = a\n\tpkgver = 2\n",
+            "pkgbase = a\njunk\npkgname = a\n",
+        ] {
+            assert!(SrcInfo::parse(text).is_err(), "accepted {:?}", text);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// vercmp.rs - pacman version comparison (port of libalpm's alpm_pkg_vercmp)
+use std::cmp::Ordering;
+
+// Split "epoch:pkgver-pkgrel" into its parts. A missing epoch is "0", a missing pkgrel is None.
+fn parse_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let digits = evr
+        .bytes()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    let (epoch, rest) = if evr[digits..].starts_with(':') {
+        let epoch = &evr[..digits];
+        (if epoch.is_empty() { "0" } else { epoch }, &evr[digits + 1..])
+    } else {
+        ("0", evr)
+    };
+    match rest.rfind('-') {
+        Some(idx) => (epoch, &rest[..idx], Some(&rest[idx + 1..])),
+        None => (epoch, rest, None),
+    }
+}
+
+// Compare two version segments the way rpmvercmp does: alternating runs of digits and letters,
+// separators only matter by their length, numbers beat letters, and a trailing alpha run is older.
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+    let (mut ptr1, mut ptr2) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        // ignore non-alphanumeric characters
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+
+        // ran to the end of either: finished with the loop
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+
+        // different separator lengths decide the comparison
+        if one - ptr1 != two - ptr2 {
+            return (one - ptr1).cmp(&(two - ptr2));
+        }
+
+        ptr1 = one;
+        ptr2 = two;
+
+        let isnum = a[ptr1].is_ascii_digit();
+        if isnum {
+            while ptr1 < a.len() && a[ptr1].is_ascii_digit() {
+                ptr1 += 1;
+            }
+            while ptr2 < b.len() && b[ptr2].is_ascii_digit() {
+                ptr2 += 1;
+            }
+        } else {
+            while ptr1 < a.len() && a[ptr1].is_ascii_alphabetic() {
+                ptr1 += 1;
+            }
+            while ptr2 < b.len() && b[ptr2].is_ascii_alphabetic() {
+                ptr2 += 1;
+            }
+        }
+
+        // segments of different types: numeric is always newer than alpha
+        if two == ptr2 {
+            return if isnum { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let mut seg1 = &a[one..ptr1];
+        let mut seg2 = &b[two..ptr2];
+        if isnum {
+            while seg1.first() == Some(&b'0') {
+                seg1 = &seg1[1..];
+            }
+            while seg2.first() == Some(&b'0') {
+                seg2 = &seg2[1..];
+            }
+            // the longer number (without leading zeros) wins
+            match seg1.len().cmp(&seg2.len()) {
+                Ordering::Equal => {}
+                other => {
+                    return other;
+                }
+            }
+        }
+
+        match seg1.cmp(seg2) {
+            Ordering::Equal => {}
+            other => {
+                return other;
+            }
+        }
+
+        one = ptr1;
+        two = ptr2;
+    }
+
+    if one >= a.len() && two >= b.len() {
+        return Ordering::Equal;
+    }
+
+    // the final showdown: a remaining alpha string never beats an empty string.
+    // - if a is empty and b is not an alpha, b is newer
+    // - if a is an alpha, b is newer
+    // - otherwise a is newer
+    let a_alpha = one < a.len() && a[one].is_ascii_alphabetic();
+    let b_alpha = two < b.len() && b[two].is_ascii_alphabetic();
+    if (one >= a.len() && !b_alpha) || a_alpha {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+// Compare two full package versions ("[epoch:]pkgver[-pkgrel]") like `vercmp` / libalpm.
+// pkgrel is only compared when both versions have one.
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch1, ver1, rel1) = parse_evr(a);
+    let (epoch2, ver2, rel2) = parse_evr(b);
+
+    rpmvercmp(epoch1, epoch2)
+        .then_with(|| rpmvercmp(ver1, ver2))
+        .then_with(|| {
+            match (rel1, rel2) {
+                (Some(r1), Some(r2)) => rpmvercmp(r1, r2),
+                _ => Ordering::Equal,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pacman's test/util/vercmptest.sh; every pair is also checked the other way round
+    const VECTORS: &[(&str, &str, i8)] = &[
+        // all similar length, no pkgrel
+        ("1.5.0", "1.5.0", 0),
+        ("1.5.1", "1.5.0", 1),
+        // mixed length
+        ("1.5.1", "1.5", 1),
+        // with pkgrel, simple
+        ("1.5.0-1", "1.5.0-1", 0),
+        ("1.5.0-1", "1.5.0-2", -1),
+        ("1.5.0-1", "1.5.1-1", -1),
+        ("1.5.0-2", "1.5.1-1", -1),
+        // with pkgrel, mixed lengths
+        ("1.5-1", "1.5.1-1", -1),
+        ("1.5-2", "1.5.1-1", -1),
+        ("1.5-2", "1.5.1-2", -1),
+        // mixed pkgrel inclusion
+        ("1.5", "1.5-1", 0),
+        ("1.5-1", "1.5", 0),
+        ("1.1-1", "1.1", 0),
+        ("1.0-1", "1.1", -1),
+        ("1.1-1", "1.0", 1),
+        // alphanumeric versions
+        ("1.5b-1", "1.5-1", -1),
+        ("1.5b", "1.5", -1),
+        ("1.5b-1", "1.5", -1),
+        ("1.5b", "1.5.1", -1),
+        // from the manpage
+        ("1.0a", "1.0alpha", -1),
+        ("1.0alpha", "1.0b", -1),
+        ("1.0b", "1.0beta", -1),
+        ("1.0beta", "1.0rc", -1),
+        ("1.0rc", "1.0", -1),
+        // alpha-dotted versions
+        ("1.5.a", "1.5", 1),
+        ("1.5.b", "1.5.a", 1),
+        ("1.5.1", "1.5.b", 1),
+        // alpha dots and dashes
+        ("1.5.b-1", "1.5.b", 0),
+        ("1.5-1", "1.5.b", -1),
+        // same/similar content, differing separators
+        ("2.0", "2_0", 0),
+        ("2.0_a", "2_0.a", 0),
+        ("2.0a", "2.0.a", -1),
+        ("2___a", "2_a", 1),
+        // epoch included version comparisons
+        ("0:1.0", "0:1.0", 0),
+        ("0:1.0", "0:1.1", -1),
+        ("1:1.0", "0:1.0", 1),
+        ("1:1.0", "0:1.1", 1),
+        ("1:1.0", "2:1.1", -1),
+        // epoch + sometimes present pkgrel
+        ("1:1.0", "0:1.0-1", 1),
+        ("1:1.0-1", "0:1.1-1", 1),
+        // epoch included on one version
+        ("0:1.0", "1.0", 0),
+        ("0:1.0", "1.1", -1),
+        ("0:1.1", "1.0", 1),
+        ("1:1.0", "1.0", 1),
+        ("1:1.0", "1.1", 1),
+        ("1:1.1", "1.1", 1),
+    ];
+
+    #[test]
+    fn pacman_vectors() {
+        for &(a, b, expected) in VECTORS {
+            let expected = expected.cmp(&0);
+            assert_eq!(vercmp(a, b), expected, "vercmp({}, {})", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "vercmp({}, {})", b, a);
+        }
+    }
+}